@@ -1,14 +1,30 @@
 pub mod assembly;
+pub mod binding;
 pub mod bytes;
+pub mod conditional;
 pub mod define;
+pub mod diagnostic;
+pub mod hex;
+pub mod iteration;
+pub mod layout;
+pub mod module;
 pub mod repeat;
+pub mod sized;
+pub mod source;
+pub mod structure;
+pub mod text;
 
 use crate::error::EvaluationError;
 use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
 use std::rc::Rc;
 
 pub trait Block {
     fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError>;
+
+    fn evaluate_value(&self, scope: &mut EvaluatorScope) -> Result<Value, EvaluationError> {
+        Ok(Value::Bytes(self.evaluate(scope)?))
+    }
 }
 
 pub trait MacroBlock {
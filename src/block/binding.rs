@@ -0,0 +1,40 @@
+use crate::block::{Block, MacroBlock};
+use crate::error::EvaluationError;
+use crate::evaluator::evaluate_value;
+use crate::evaluator::expansion::InlineExpansion;
+use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
+use std::rc::Rc;
+
+pub struct LetBlock {
+    name: String,
+    blocks: Vec<Rc<dyn Block>>,
+}
+
+impl Block for LetBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        let value: Value = evaluate_value(&self.blocks, scope)?;
+        scope.set(&self.name, InlineExpansion::new(self.name.clone(), value));
+        Ok(Vec::new())
+    }
+}
+
+impl MacroBlock for LetBlock {
+    fn allocate(
+        line_number: usize,
+        mut args: Vec<String>,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        if args.len() == 1 {
+            Ok(Rc::new(Self {
+                name: args.remove(0),
+                blocks,
+            }))
+        } else {
+            Err(EvaluationError::new(
+                line_number,
+                "expected exactly one argument indicating binding name".to_string(),
+            ))
+        }
+    }
+}
@@ -1,12 +1,24 @@
+mod builtin;
+pub mod condition;
+pub mod expression;
+pub mod iterable;
 mod parser;
-mod translate;
+pub mod translate;
 
 use super::Block;
-use crate::block::bytes::parser::{parse_bytes, BytesItem};
-use crate::error::AnonymousEvaluationErrorResult;
+use crate::block::bytes::builtin::expand_builtin;
+use crate::block::bytes::expression::Expression;
+use crate::block::bytes::parser::{parse_bytes, Argument, BytesItem, Number};
+pub use crate::block::bytes::parser::{parse_message, parse_padding, parse_parameters};
+use crate::block::bytes::translate::{
+    decode_number, encode_integer, is_float, size_from_integer, Sizing,
+};
 use crate::error::EvaluationError;
-use crate::evaluator::expansion::Expansion;
-use crate::evaluator::scope::EvaluatorScope;
+use crate::error::{AnonymousEvaluationError, AnonymousEvaluationErrorResult};
+use crate::evaluator::expansion::{Arguments, Expansion, Thunk};
+use crate::evaluator::scope::{Environment, EvaluatorScope};
+use crate::evaluator::value::Value;
+use std::rc::Rc;
 
 pub struct BytesBlock {
     line_number: usize,
@@ -29,14 +41,132 @@ fn reverse_tail(vector: &mut Vec<u8>, from: usize) {
     }
 }
 
-fn evaluate(
-    line_number: usize,
+fn resolve_sizing(
+    sizing: &Sizing<Expression>,
+    scope: &EvaluatorScope,
+) -> Result<Sizing<usize>, AnonymousEvaluationError> {
+    match sizing {
+        Sizing::Unsized => Ok(Sizing::Unsized),
+        Sizing::AnchoredRight(size) => Ok(Sizing::AnchoredRight(size_from_integer(
+            &size.evaluate(scope)?,
+        )?)),
+        Sizing::AnchoredLeft(size) => Ok(Sizing::AnchoredLeft(size_from_integer(
+            &size.evaluate(scope)?,
+        )?)),
+    }
+}
+
+pub fn expand(
+    name: &str,
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    match scope.get(name) {
+        Some(expansion) => {
+            let mut expansion_args: Arguments = Arguments {
+                scope,
+                positional: Vec::new(),
+                named: Vec::new(),
+            };
+            for arg in args {
+                match &arg.name {
+                    Some(name) => expansion_args.named.push((name.clone(), arg)),
+                    None if expansion_args.named.is_empty() => expansion_args.positional.push(arg),
+                    None => {
+                        return Err(AnonymousEvaluationError::new(format!(
+                            "positional argument {} follows named arguments to ${}",
+                            arg.text, name
+                        )))
+                    }
+                }
+            }
+            expansion.expand(scope, &expansion_args)
+        }
+        None => expand_builtin(name, args, scope),
+    }
+}
+
+struct LazyExpansion {
+    text: String,
+    items: Rc<Vec<BytesItem>>,
+    environment: Rc<Environment>,
+}
+
+impl Expansion for LazyExpansion {
+    fn expand(
+        &self,
+        scope: &EvaluatorScope,
+        args: &Arguments,
+    ) -> Result<Value, AnonymousEvaluationError> {
+        if !args.is_empty() {
+            return Err(AnonymousEvaluationError::new(format!(
+                "lazy argument {} expected 0 args, got {}",
+                self.text,
+                args.len()
+            )));
+        }
+        let inner: EvaluatorScope = scope.detached(Some(self.environment.clone()));
+        evaluate_value(&self.items, &inner)
+    }
+}
+
+impl Thunk for Argument {
+    fn force(&self, scope: &EvaluatorScope) -> Result<Value, AnonymousEvaluationError> {
+        evaluate_value(&self.items, scope)
+    }
+
+    fn delay(&self, scope: &EvaluatorScope) -> Box<dyn Expansion> {
+        Box::new(LazyExpansion {
+            text: self.text.clone(),
+            items: self.items.clone(),
+            environment: scope.capture(),
+        })
+    }
+}
+
+fn evaluate_item(
+    item: &BytesItem,
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    match item {
+        BytesItem::Literal(bytes) => Ok(Value::Bytes(bytes.clone())),
+        BytesItem::Numeral(bytes) => Ok(Value::Numeral(bytes.clone())),
+        BytesItem::String(string) => Ok(Value::String(string.clone())),
+        BytesItem::Number(Number::Literal(literal), sizing) => {
+            let bytes: Vec<u8> = decode_number(literal, resolve_sizing(sizing, scope)?)?;
+            if is_float(literal) {
+                Ok(Value::Bytes(bytes))
+            } else {
+                Ok(Value::Numeral(bytes))
+            }
+        }
+        BytesItem::Number(Number::Expression(expression), Sizing::Unsized) => {
+            Ok(Value::Integer(expression.evaluate(scope)?))
+        }
+        BytesItem::Number(Number::Expression(expression), sizing) => Ok(Value::Numeral(
+            encode_integer(&expression.evaluate(scope)?, resolve_sizing(sizing, scope)?)?,
+        )),
+        BytesItem::Typed(integer_type, expression) => Ok(Value::Bytes(
+            integer_type.encode(&expression.evaluate(scope)?)?,
+        )),
+        BytesItem::Expansion(name, args) => expand(name, args, scope),
+        BytesItem::Word(word) => Err(AnonymousEvaluationError::new(format!(
+            "unexpected word {}",
+            word
+        ))),
+        BytesItem::Left | BytesItem::Right => Ok(Value::Bytes(Vec::new())),
+    }
+}
+
+pub fn evaluate(
     items: &Vec<BytesItem>,
-    scope: &mut EvaluatorScope,
-) -> Result<Vec<u8>, EvaluationError> {
+    scope: &EvaluatorScope,
+) -> Result<Vec<u8>, AnonymousEvaluationError> {
     let mut result: Vec<u8> = Vec::new();
     let mut flip: Option<usize> = None;
+    let origin: usize = scope.layout().position();
     for item in items.iter() {
+        scope.layout().seek(origin + result.len());
         match item {
             BytesItem::Left => {
                 if let Some(start) = flip {
@@ -50,34 +180,35 @@ fn evaluate(
                     flip = None;
                 }
             }
-            BytesItem::Literal(bytes) => {
-                result.extend(bytes);
-            }
-            BytesItem::Expansion(name, args) => {
-                let mut expansion_args: Vec<Vec<u8>> = Vec::new();
-                for arg in args {
-                    expansion_args.push(evaluate(line_number, arg, scope)?);
-                }
-
-                let expansion: &Box<dyn Expansion> = scope.get(&name).ok_or(
-                    EvaluationError::new(line_number, format!("undefined variable {}", name)),
-                )?;
-                result.extend(
-                    expansion
-                        .expand(scope, &expansion_args)
-                        .map_err_at(line_number)?,
-                );
+            _ => {
+                result.extend(evaluate_item(item, scope)?.to_bytes()?);
             }
         }
     }
     if let Some(start) = flip {
         reverse_tail(&mut result, start);
     }
+    scope.layout().seek(origin);
     Ok(result)
 }
 
+pub fn evaluate_value(
+    items: &Vec<BytesItem>,
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    match items.as_slice() {
+        [BytesItem::Left] | [BytesItem::Right] => Ok(Value::Bytes(Vec::new())),
+        [item] => evaluate_item(item, scope),
+        _ => Ok(Value::Bytes(evaluate(items, scope)?)),
+    }
+}
+
 impl Block for BytesBlock {
     fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
-        evaluate(self.line_number, &self.items, scope)
+        evaluate(&self.items, scope).map_err_at(self.line_number)
+    }
+
+    fn evaluate_value(&self, scope: &mut EvaluatorScope) -> Result<Value, EvaluationError> {
+        evaluate_value(&self.items, scope).map_err_at(self.line_number)
     }
 }
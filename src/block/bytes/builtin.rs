@@ -0,0 +1,180 @@
+use crate::block::bytes::evaluate_value;
+use crate::block::bytes::parser::Argument;
+use crate::block::bytes::translate::IntegerType;
+use crate::error::AnonymousEvaluationError;
+use crate::evaluator::expansion::StructLayout;
+use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
+use num_bigint::{BigInt, Sign};
+
+fn expect_args(
+    name: &str,
+    args: &[Argument],
+    minimum: usize,
+    maximum: usize,
+) -> Result<(), AnonymousEvaluationError> {
+    if args.len() < minimum || args.len() > maximum {
+        Err(AnonymousEvaluationError::new(format!(
+            "expansion ${} expected {} args, got {}",
+            name,
+            if minimum == maximum {
+                minimum.to_string()
+            } else {
+                format!("{} to {}", minimum, maximum)
+            },
+            args.len()
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+// $pack(type, value) encodes an integer value as a typed integer
+fn expand_pack(
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    expect_args("pack", args, 2, 2)?;
+    let integer_type: IntegerType = IntegerType::from_name(&args[0].text)?;
+    let value: BigInt = evaluate_value(&args[1].items, scope)?.to_integer()?;
+    Ok(Value::Bytes(integer_type.encode(&value)?))
+}
+
+// $int(bytes, [be|le]) reads bytes as an unsigned integer, big-endian by default
+fn expand_int(
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    expect_args("int", args, 1, 2)?;
+    let bytes: Vec<u8> = evaluate_value(&args[0].items, scope)?.to_bytes()?;
+    match args.get(1).map(|arg| arg.text.as_str()) {
+        None | Some("be") => Ok(Value::Integer(BigInt::from_bytes_be(Sign::Plus, &bytes))),
+        Some("le") => Ok(Value::Integer(BigInt::from_bytes_le(Sign::Plus, &bytes))),
+        Some(other) => Err(AnonymousEvaluationError::new(format!(
+            "expected byte order be or le, got {}",
+            other
+        ))),
+    }
+}
+
+// $len(type, bytes) encodes the length of a byte expression as a typed integer
+fn expand_len(
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    expect_args("len", args, 2, 2)?;
+    let integer_type: IntegerType = IntegerType::from_name(&args[0].text)?;
+    let length: usize = evaluate_value(&args[1].items, scope)?.to_bytes()?.len();
+    Ok(Value::Bytes(integer_type.encode(&BigInt::from(length))?))
+}
+
+fn with_structure<T>(
+    arg: &Argument,
+    scope: &EvaluatorScope,
+    f: impl FnOnce(&dyn StructLayout) -> Result<T, AnonymousEvaluationError>,
+) -> Result<T, AnonymousEvaluationError> {
+    let name: String = arg.text.trim_start_matches('$').replace("::", ".");
+    match scope.get(&name) {
+        Some(expansion) if expansion.structure().is_some() => f(expansion.structure().unwrap()),
+        _ => Err(AnonymousEvaluationError::new(format!(
+            "undefined struct {}",
+            name
+        ))),
+    }
+}
+
+// $sizeof(struct) is the size of a struct including trailing padding
+fn expand_sizeof(
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    expect_args("sizeof", args, 1, 1)?;
+    with_structure(&args[0], scope, |structure| {
+        Ok(Value::Integer(BigInt::from(structure.size())))
+    })
+}
+
+// $offsetof(struct, field) is the offset of a field from the start of its struct
+fn expand_offsetof(
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    expect_args("offsetof", args, 2, 2)?;
+    with_structure(&args[0], scope, |structure| {
+        match structure.offset(&args[1].text) {
+            Some(offset) => Ok(Value::Integer(BigInt::from(offset))),
+            None => Err(AnonymousEvaluationError::new(format!(
+                "struct {} has no field named {}",
+                structure.name(),
+                args[1].text
+            ))),
+        }
+    })
+}
+
+// A label is named by a bare word or by a string value, such as a definition parameter
+fn label_name(arg: &Argument, scope: &EvaluatorScope) -> Result<String, AnonymousEvaluationError> {
+    if !arg
+        .text
+        .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+    {
+        if let Value::String(name) = evaluate_value(&arg.items, scope)? {
+            return Ok(name);
+        }
+    }
+    Ok(arg.text.clone())
+}
+
+// $here is the address at which the expansion is emitted
+fn expand_here(
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    expect_args("here", args, 0, 0)?;
+    let layout = scope.layout();
+    Ok(Value::Integer(layout.base() + layout.position()))
+}
+
+// $offset(label) is the label's offset from the start of the payload
+fn expand_offset(
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    expect_args("offset", args, 1, 1)?;
+    Ok(Value::Integer(BigInt::from(
+        scope.label_offset(&label_name(&args[0], scope)?)?,
+    )))
+}
+
+// $addr(label) is the label's address relative to @base
+fn expand_addr(
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    expect_args("addr", args, 1, 1)?;
+    let layout = scope.layout();
+    Ok(Value::Integer(
+        layout.base() + scope.label_offset(&label_name(&args[0], scope)?)?,
+    ))
+}
+
+pub fn expand_builtin(
+    name: &str,
+    args: &[Argument],
+    scope: &EvaluatorScope,
+) -> Result<Value, AnonymousEvaluationError> {
+    match name {
+        "pack" => expand_pack(args, scope),
+        "int" => expand_int(args, scope),
+        "len" => expand_len(args, scope),
+        "sizeof" => expand_sizeof(args, scope),
+        "offsetof" => expand_offsetof(args, scope),
+        "here" => expand_here(args, scope),
+        "offset" => expand_offset(args, scope),
+        "addr" => expand_addr(args, scope),
+        _ => Err(AnonymousEvaluationError::new(format!(
+            "undefined variable {}",
+            name
+        ))),
+    }
+}
@@ -0,0 +1,115 @@
+use crate::block::bytes::parser::{parse_assertion, parse_condition, BytesItem};
+use crate::block::bytes::{evaluate, evaluate_item};
+use crate::error::AnonymousEvaluationError;
+use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
+use num_bigint::BigInt;
+use num_traits::Zero;
+use std::cmp::Ordering;
+
+pub enum Comparator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+pub enum Term {
+    Item(BytesItem),
+    Length(Vec<BytesItem>),
+}
+
+pub enum Condition {
+    Truthy(Term),
+    Compare(Term, Comparator, Term),
+    Defined(String),
+    Contains(Vec<BytesItem>, Vec<BytesItem>),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+impl Comparator {
+    fn test(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparator::Equal => ordering == Ordering::Equal,
+            Comparator::NotEqual => ordering != Ordering::Equal,
+            Comparator::Less => ordering == Ordering::Less,
+            Comparator::LessEqual => ordering != Ordering::Greater,
+            Comparator::Greater => ordering == Ordering::Greater,
+            Comparator::GreaterEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl Term {
+    fn evaluate(&self, scope: &EvaluatorScope) -> Result<Value, AnonymousEvaluationError> {
+        match self {
+            Term::Item(item) => evaluate_item(item, scope),
+            Term::Length(items) => Ok(Value::Integer(BigInt::from(evaluate(items, scope)?.len()))),
+        }
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Result<Ordering, AnonymousEvaluationError> {
+    match (left, right) {
+        (Value::Integer(_), _) | (_, Value::Integer(_)) => {
+            Ok(left.to_integer()?.cmp(&right.to_integer()?))
+        }
+        (Value::String(left), Value::String(right)) => Ok(left.cmp(right)),
+        _ => Ok(left.to_bytes()?.cmp(&right.to_bytes()?)),
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Integer(value) => !value.is_zero(),
+        Value::Bytes(bytes) | Value::Numeral(bytes) => bytes.iter().any(|byte| *byte != 0),
+        Value::String(string) => !string.is_empty(),
+        Value::List(values) => !values.is_empty(),
+    }
+}
+
+impl Condition {
+    pub fn new(line: &str) -> Result<Self, AnonymousEvaluationError> {
+        parse_condition(line)
+    }
+
+    pub fn with_message(line: &str) -> Result<(Self, Option<String>), AnonymousEvaluationError> {
+        parse_assertion(line)
+    }
+
+    pub fn evaluate(&self, scope: &EvaluatorScope) -> Result<bool, AnonymousEvaluationError> {
+        match self {
+            Condition::Truthy(term) => Ok(is_truthy(&term.evaluate(scope)?)),
+            Condition::Compare(left, comparator, right) => {
+                Ok(comparator.test(compare(&left.evaluate(scope)?, &right.evaluate(scope)?)?))
+            }
+            Condition::Defined(name) => Ok(scope.get(name).is_some()),
+            Condition::Contains(haystack, needle) => {
+                let haystack: Vec<u8> = evaluate(haystack, scope)?;
+                let needle: Vec<u8> = evaluate(needle, scope)?;
+                Ok(needle.is_empty() || haystack.windows(needle.len()).any(|w| w == needle))
+            }
+            Condition::Not(condition) => Ok(!condition.evaluate(scope)?),
+            Condition::All(conditions) => {
+                for condition in conditions {
+                    if !condition.evaluate(scope)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Condition::Any(conditions) => {
+                for condition in conditions {
+                    if condition.evaluate(scope)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+}
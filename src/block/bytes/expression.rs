@@ -0,0 +1,87 @@
+use crate::block::bytes::expand;
+use crate::block::bytes::parser::{parse_count, Argument};
+use crate::error::AnonymousEvaluationError;
+use crate::evaluator::scope::EvaluatorScope;
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+
+pub enum UnaryOperator {
+    Negate,
+    Invert,
+}
+
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    ShiftLeft,
+    ShiftRight,
+    And,
+    Or,
+    Xor,
+}
+
+pub enum Expression {
+    Literal(BigInt),
+    Reference(String, Vec<Argument>),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+// Left shifts grow the value, so they are capped well above any sensible field width
+const MAX_SHIFT: usize = 0x4000;
+
+fn shift_amount(value: &BigInt, limit: usize) -> Result<usize, AnonymousEvaluationError> {
+    match value.to_usize() {
+        Some(amount) if amount <= limit => Ok(amount),
+        Some(_) => Err(AnonymousEvaluationError::new(format!(
+            "shift amount {} exceeds the limit of {} bits",
+            value, limit
+        ))),
+        None => Err(AnonymousEvaluationError::new(format!(
+            "invalid shift amount {}",
+            value
+        ))),
+    }
+}
+
+impl Expression {
+    pub fn new(line: &str) -> Result<Self, AnonymousEvaluationError> {
+        parse_count(line)
+    }
+
+    pub fn evaluate(&self, scope: &EvaluatorScope) -> Result<BigInt, AnonymousEvaluationError> {
+        match self {
+            Expression::Literal(value) => Ok(value.clone()),
+            Expression::Reference(name, args) => expand(name, args, scope)?.to_integer(),
+            Expression::Unary(operator, operand) => {
+                let value: BigInt = operand.evaluate(scope)?;
+                Ok(match operator {
+                    UnaryOperator::Negate => -value,
+                    UnaryOperator::Invert => !value,
+                })
+            }
+            Expression::Binary(operator, left, right) => {
+                let left: BigInt = left.evaluate(scope)?;
+                let right: BigInt = right.evaluate(scope)?;
+                match operator {
+                    BinaryOperator::Add => Ok(left + right),
+                    BinaryOperator::Subtract => Ok(left - right),
+                    BinaryOperator::Multiply => Ok(left * right),
+                    BinaryOperator::Divide | BinaryOperator::Remainder if right.is_zero() => Err(
+                        AnonymousEvaluationError::new("division by zero".to_string()),
+                    ),
+                    BinaryOperator::Divide => Ok(left / right),
+                    BinaryOperator::Remainder => Ok(left % right),
+                    BinaryOperator::ShiftLeft => Ok(left << shift_amount(&right, MAX_SHIFT)?),
+                    BinaryOperator::ShiftRight => Ok(left >> shift_amount(&right, usize::MAX)?),
+                    BinaryOperator::And => Ok(left & right),
+                    BinaryOperator::Or => Ok(left | right),
+                    BinaryOperator::Xor => Ok(left ^ right),
+                }
+            }
+        }
+    }
+}
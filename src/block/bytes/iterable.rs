@@ -0,0 +1,47 @@
+use crate::block::bytes::expression::Expression;
+use crate::block::bytes::parser::{parse_iterable, BytesItem};
+use crate::block::bytes::{evaluate_item, evaluate_value};
+use crate::error::AnonymousEvaluationError;
+use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
+use num_bigint::BigInt;
+
+pub enum Iterable {
+    Range(Expression, Expression),
+    List(Vec<Vec<BytesItem>>),
+    Expansion(BytesItem),
+}
+
+impl Iterable {
+    pub fn new(line: &str) -> Result<Self, AnonymousEvaluationError> {
+        parse_iterable(line)
+    }
+
+    pub fn values(&self, scope: &EvaluatorScope) -> Result<Vec<Value>, AnonymousEvaluationError> {
+        let mut result: Vec<Value> = Vec::new();
+        match self {
+            Iterable::Range(start, end) => {
+                let mut index: BigInt = start.evaluate(scope)?;
+                let end: BigInt = end.evaluate(scope)?;
+                while index < end {
+                    result.push(Value::Integer(index.clone()));
+                    index += 1;
+                }
+            }
+            Iterable::List(elements) => {
+                for items in elements {
+                    result.push(evaluate_value(items, scope)?);
+                }
+            }
+            Iterable::Expansion(item) => match evaluate_item(item, scope)? {
+                Value::List(values) => result = values,
+                _ => {
+                    return Err(AnonymousEvaluationError::new(
+                        "expected a list to iterate over".to_string(),
+                    ))
+                }
+            },
+        }
+        Ok(result)
+    }
+}
@@ -1,12 +1,40 @@
+use pest::error::LineColLocation;
 use pest::iterators::Pair;
 use pest::Parser;
+use std::iter::Enumerate;
+use std::rc::Rc;
+use std::str::Chars;
 
-use crate::block::bytes::translate::{bytes_from_number, integer_from_number};
+use crate::block::bytes::condition::{Comparator, Condition, Term};
+use crate::block::bytes::expression::{BinaryOperator, Expression, UnaryOperator};
+use crate::block::bytes::iterable::Iterable;
+use crate::block::bytes::translate::{
+    big_integer_from_number, byte_from_hexadecimal_digit, bytes_from_base64, decode_number,
+    is_float, size_from_integer, Charset, IntegerType, Sizing,
+};
 use crate::error::AnonymousEvaluationError;
+use num_bigint::BigInt;
+
+pub struct Argument {
+    pub name: Option<String>,
+    pub text: String,
+    pub items: Rc<Vec<BytesItem>>,
+}
+
+pub enum Number {
+    Literal(String),
+    Expression(Expression),
+}
 
 pub enum BytesItem {
-    Expansion(String, Vec<Vec<BytesItem>>),
+    Expansion(String, Vec<Argument>),
     Literal(Vec<u8>),
+    // A number literal decoded to bytes ahead of time
+    Numeral(Vec<u8>),
+    String(String),
+    Number(Number, Sizing<Expression>),
+    Typed(IntegerType, Expression),
+    Word(String),
     Left,
     Right,
 }
@@ -15,71 +43,375 @@ pub enum BytesItem {
 #[grammar = "block/bytes/bytes.pest"]
 struct BytesParser;
 
-fn resize_anchored_right(literal: &mut Vec<u8>, size: usize) {
-    literal.reverse();
-    literal.resize(size, 0);
-    literal.reverse();
-}
-
-fn resize_anchored_left(literal: &mut Vec<u8>, size: usize) {
-    literal.resize(size, 0);
-}
-
-fn decode_number(string: &str) -> Result<Vec<u8>, AnonymousEvaluationError> {
-    if string.starts_with("[") {
-        let size_end: usize = string.find("]").ok_or(AnonymousEvaluationError::new(
-            "invalid padding format".to_string(),
-        ))?;
-        let size: usize = integer_from_number(&string[1..size_end])?;
-        let mut result: Vec<u8> = bytes_from_number(&string[size_end + 1..], true)?;
-        resize_anchored_right(&mut result, size);
-        Ok(result)
-    } else if string.ends_with("]") {
-        let size_start: usize = string.find("[").ok_or(AnonymousEvaluationError::new(
-            "invalid padding format".to_string(),
-        ))?;
-        let size: usize = integer_from_number(&string[size_start + 1..string.len() - 1])?;
-        let mut result: Vec<u8> = bytes_from_number(&string[..size_start], true)?;
-        resize_anchored_left(&mut result, size);
-        Ok(result)
+fn strip_separators(string: &str) -> String {
+    string
+        .replace("x\"", "0x")
+        .chars()
+        .filter(|c| !(*c == '_' || *c == '"' || c.is_whitespace()))
+        .collect()
+}
+
+// Checks the digits of a quoted hexadecimal blob, locating errors on the line they occur
+fn check_blob(pair: &Pair<Rule>) -> Result<(), AnonymousEvaluationError> {
+    let text: &str = pair.as_str().trim_start_matches('-');
+    let digits: &str = match text.strip_prefix("x\"") {
+        Some(digits) => digits.trim_end_matches('"'),
+        None => return Ok(()),
+    };
+    let mut line: usize = pair.as_span().start_pos().line_col().0 - 1;
+    let mut last: usize = line;
+    let mut count: usize = 0;
+    for character in digits.chars() {
+        match character {
+            '\n' => line += 1,
+            '_' | ' ' | '\t' | '\r' => {}
+            digit if digit.is_ascii_hexdigit() => {
+                count += 1;
+                last = line;
+            }
+            other => {
+                return Err(AnonymousEvaluationError::new(format!(
+                    "invalid hexadecimal digit {:?} in blob",
+                    other
+                ))
+                .below(line))
+            }
+        }
+    }
+    if count.is_multiple_of(2) {
+        Ok(())
     } else {
-        bytes_from_number(&string, true)
+        Err(AnonymousEvaluationError::new(
+            "length of hexadecimal word must be divisible by two".to_string(),
+        )
+        .below(last))
+    }
+}
+
+fn binary_operator(pair: Pair<Rule>) -> Result<BinaryOperator, AnonymousEvaluationError> {
+    match pair.as_rule() {
+        Rule::plus => Ok(BinaryOperator::Add),
+        Rule::minus => Ok(BinaryOperator::Subtract),
+        Rule::times => Ok(BinaryOperator::Multiply),
+        Rule::divide => Ok(BinaryOperator::Divide),
+        Rule::modulo => Ok(BinaryOperator::Remainder),
+        Rule::shift_left => Ok(BinaryOperator::ShiftLeft),
+        Rule::shift_right => Ok(BinaryOperator::ShiftRight),
+        Rule::bit_and => Ok(BinaryOperator::And),
+        Rule::bit_or => Ok(BinaryOperator::Or),
+        Rule::bit_xor => Ok(BinaryOperator::Xor),
+        _ => Err(AnonymousEvaluationError::new(format!(
+            "unexpected operator {:?}",
+            pair.as_rule()
+        ))),
+    }
+}
+
+fn parse_expression(pair: Pair<Rule>) -> Result<Expression, AnonymousEvaluationError> {
+    match pair.as_rule() {
+        Rule::expression => parse_expression(pair.into_inner().next().unwrap()),
+        Rule::bitwise_or
+        | Rule::bitwise_xor
+        | Rule::bitwise_and
+        | Rule::shift
+        | Rule::sum
+        | Rule::product => {
+            let mut inner_pairs = pair.into_inner();
+            let mut result: Expression = parse_expression(inner_pairs.next().unwrap())?;
+            while let Some(operator) = inner_pairs.next() {
+                result = Expression::Binary(
+                    binary_operator(operator)?,
+                    Box::new(result),
+                    Box::new(parse_expression(inner_pairs.next().unwrap())?),
+                );
+            }
+            Ok(result)
+        }
+        Rule::unary => {
+            let mut inner_pairs: Vec<Pair<Rule>> = pair.into_inner().collect();
+            let mut result: Expression = parse_expression(inner_pairs.pop().unwrap())?;
+            while let Some(operator) = inner_pairs.pop() {
+                let operator: UnaryOperator = match operator.as_rule() {
+                    Rule::minus => UnaryOperator::Negate,
+                    _ => UnaryOperator::Invert,
+                };
+                result = Expression::Unary(operator, Box::new(result));
+            }
+            Ok(result)
+        }
+        Rule::character => Ok(Expression::Literal(BigInt::from(decode_character(pair)?))),
+        Rule::expansion => {
+            let (name, args): (String, Vec<Argument>) = parse_expansion_parts(pair)?;
+            Ok(Expression::Reference(name, args))
+        }
+        _ => Ok(Expression::Literal(big_integer_from_number(
+            &strip_separators(pair.as_str()),
+        )?)),
+    }
+}
+
+fn parse_size(pair: Pair<Rule>) -> Result<Expression, AnonymousEvaluationError> {
+    let inner: Pair<Rule> = pair.into_inner().next().unwrap();
+    match inner.as_rule() {
+        Rule::expression | Rule::expansion => parse_expression(inner),
+        _ => Ok(Expression::Literal(big_integer_from_number(
+            &strip_separators(inner.as_str()),
+        )?)),
+    }
+}
+
+fn constant_sizing(
+    sizing: &Sizing<Expression>,
+) -> Result<Option<Sizing<usize>>, AnonymousEvaluationError> {
+    match sizing {
+        Sizing::Unsized => Ok(Some(Sizing::Unsized)),
+        Sizing::AnchoredRight(Expression::Literal(size)) => {
+            Ok(Some(Sizing::AnchoredRight(size_from_integer(size)?)))
+        }
+        Sizing::AnchoredLeft(Expression::Literal(size)) => {
+            Ok(Some(Sizing::AnchoredLeft(size_from_integer(size)?)))
+        }
+        _ => Ok(None),
     }
 }
 
 fn parse_number(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationError> {
-    Ok(BytesItem::Literal(decode_number(pair.as_str())?))
+    let mut sizing: Sizing<Expression> = Sizing::Unsized;
+    let mut value: Option<Pair<Rule>> = None;
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::sizing if value.is_none() => sizing = Sizing::AnchoredRight(parse_size(inner)?),
+            Rule::sizing => sizing = Sizing::AnchoredLeft(parse_size(inner)?),
+            _ => value = Some(inner),
+        }
+    }
+
+    let value: Pair<Rule> = value.unwrap();
+    if value.as_rule() == Rule::expression {
+        return Ok(BytesItem::Number(
+            Number::Expression(parse_expression(value)?),
+            sizing,
+        ));
+    }
+
+    check_blob(&value)?;
+
+    // Unsized negative literals are integers that can only be used where no size is needed
+    let literal: String = strip_separators(value.as_str());
+    if let Sizing::Unsized = sizing {
+        if literal.starts_with('-') && !is_float(&literal) {
+            return Ok(BytesItem::Number(
+                Number::Expression(Expression::Literal(big_integer_from_number(&literal)?)),
+                sizing,
+            ));
+        }
+    }
+
+    match constant_sizing(&sizing)? {
+        Some(sizing) if is_float(&literal) => {
+            Ok(BytesItem::Literal(decode_number(&literal, sizing)?))
+        }
+        Some(sizing) => Ok(BytesItem::Numeral(decode_number(&literal, sizing)?)),
+        None => Ok(BytesItem::Number(Number::Literal(literal), sizing)),
+    }
+}
+
+fn parse_typed(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationError> {
+    let mut inner_pairs = pair.into_inner();
+    let integer_type: IntegerType = IntegerType::from_name(inner_pairs.next().unwrap().as_str())?;
+    let value: Pair<Rule> = inner_pairs.next().unwrap();
+    match value.as_rule() {
+        Rule::expression | Rule::expansion => {
+            Ok(BytesItem::Typed(integer_type, parse_expression(value)?))
+        }
+        _ => Ok(BytesItem::Literal(integer_type.encode(
+            &big_integer_from_number(&strip_separators(value.as_str()))?,
+        )?)),
+    }
+}
+
+fn decode_escape_digits(
+    characters: &mut Enumerate<Chars>,
+    count: usize,
+    column: usize,
+) -> Result<u32, AnonymousEvaluationError> {
+    let mut result: u32 = 0;
+    for _ in 0..count {
+        match characters.next() {
+            Some((_, digit)) if digit.is_ascii_hexdigit() => {
+                result = (result << 4) + byte_from_hexadecimal_digit(digit)? as u32;
+            }
+            _ => {
+                return Err(AnonymousEvaluationError::new(format!(
+                    "escape sequence in column {} expected {} hexadecimal digits",
+                    column, count
+                )))
+            }
+        }
+    }
+    Ok(result)
 }
 
-fn decode_string(string: &str) -> Result<Vec<u8>, AnonymousEvaluationError> {
+fn decode_string(
+    string: &str,
+    offset: usize,
+    charset: &Charset,
+) -> Result<Vec<u8>, AnonymousEvaluationError> {
     let mut result: Vec<u8> = Vec::new();
+    let mut characters = string.chars().enumerate();
 
-    for (i, character) in string.chars().enumerate() {
-        if character > (255 as char) {
-            return Err(AnonymousEvaluationError::new(format!(
-                "encountered invalid character in column {}",
-                i
-            )));
+    while let Some((i, character)) = characters.next() {
+        let column: usize = offset + i + 1;
+        let value: u32 = if character == '\\' {
+            match characters.next() {
+                Some((_, '"')) => '"' as u32,
+                Some((_, '\'')) => '\'' as u32,
+                Some((_, '\\')) => '\\' as u32,
+                Some((_, '/')) => '/' as u32,
+                Some((_, 'b')) => 0x08,
+                Some((_, 'f')) => 0x0C,
+                Some((_, 'n')) => '\n' as u32,
+                Some((_, 'r')) => '\r' as u32,
+                Some((_, 't')) => '\t' as u32,
+                Some((_, '0')) => 0,
+                Some((_, 'x')) => {
+                    result.push(decode_escape_digits(&mut characters, 2, column)? as u8);
+                    continue;
+                }
+                Some((_, 'u')) => decode_escape_digits(&mut characters, 4, column)?,
+                Some((_, 'U')) => decode_escape_digits(&mut characters, 8, column)?,
+                Some((_, other)) => {
+                    return Err(AnonymousEvaluationError::new(format!(
+                        "invalid escape sequence \\{} in column {}",
+                        other, column
+                    )))
+                }
+                None => {
+                    return Err(AnonymousEvaluationError::new(format!(
+                        "incomplete escape sequence in column {}",
+                        column
+                    )))
+                }
+            }
         } else {
-            result.push(character as u8);
+            character as u32
+        };
+
+        match std::char::from_u32(value) {
+            Some(character) if charset.can_encode(character) => {
+                charset.encode(character, &mut result);
+            }
+            _ => {
+                return Err(AnonymousEvaluationError::new(format!(
+                    "encountered invalid character in column {}",
+                    column
+                )))
+            }
         }
     }
     Ok(result)
 }
 
+// Decodes the inside of a quoted pair, locating errors by the line and column it starts at
+fn decode_inner(
+    inner: &Pair<Rule>,
+    charset: &Charset,
+) -> Result<Vec<u8>, AnonymousEvaluationError> {
+    let (line, column): (usize, usize) = inner.as_span().start_pos().line_col();
+    decode_string(inner.as_str(), column - 1, charset).map_err(|e| e.below(line - 1))
+}
+
 fn parse_string(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationError> {
-    Ok(BytesItem::Literal(decode_string(
+    let mut inner_pairs = pair.into_inner();
+    let mut inner: Pair<Rule> = inner_pairs.next().unwrap();
+    if inner.as_rule() != Rule::encoding {
+        let result: Vec<u8> = decode_inner(&inner, &Charset::Latin1)?;
+        return Ok(BytesItem::String(
+            result.iter().map(|byte| *byte as char).collect(),
+        ));
+    }
+
+    let encoding: &str = inner.as_str();
+    let terminated: bool = encoding.starts_with('z');
+    let charset: Charset = Charset::from_name(encoding.trim_start_matches('z'))?;
+    inner = inner_pairs.next().unwrap();
+
+    let mut result: Vec<u8> = decode_inner(&inner, &charset)?;
+    if terminated {
+        charset.encode('\0', &mut result);
+    }
+    Ok(BytesItem::Literal(result))
+}
+
+fn decode_character(pair: Pair<Rule>) -> Result<u8, AnonymousEvaluationError> {
+    let (line, column): (usize, usize) = pair.as_span().start_pos().line_col();
+    let inner: Pair<Rule> = pair.into_inner().next().unwrap();
+    let result: Vec<u8> = decode_inner(&inner, &Charset::Latin1)?;
+    if result.len() != 1 {
+        return Err(AnonymousEvaluationError::new(format!(
+            "character literal in column {} must contain exactly one character",
+            column
+        ))
+        .below(line - 1));
+    }
+    Ok(result[0])
+}
+
+fn parse_character(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationError> {
+    Ok(BytesItem::Literal(vec![decode_character(pair)?]))
+}
+
+fn parse_base64(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationError> {
+    Ok(BytesItem::Literal(bytes_from_base64(
         pair.into_inner().next().unwrap().as_str(),
     )?))
 }
 
-fn parse_expansion(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationError> {
+fn parse_argument(pair: Pair<Rule>) -> Result<Argument, AnonymousEvaluationError> {
     let mut inner_pairs = pair.into_inner();
-    let name: String = String::from(inner_pairs.next().unwrap().as_str().trim_start_matches("$"));
-    let mut args: Vec<Vec<BytesItem>> = Vec::new();
-    for item in inner_pairs {
-        args.push(parse_bytes_pair_items(item)?);
+    let mut inner: Pair<Rule> = inner_pairs.next().unwrap();
+    let mut name: Option<String> = None;
+    if inner.as_rule() == Rule::keyword {
+        name = Some(String::from(inner.as_str()));
+        inner = inner_pairs.next().unwrap();
     }
+    let text: String = String::from(inner.as_str().trim());
+    let items: Vec<BytesItem> = match inner.as_rule() {
+        Rule::word => match parse_bytes(&text) {
+            Ok(items) => items,
+            Err(_) => vec![BytesItem::Word(text.clone())],
+        },
+        // An argument that is a single number, as in $sockaddr(family=2), may be an integer
+        _ if inner.clone().into_inner().count() == 1 => {
+            vec![parse_operand(inner.into_inner().next().unwrap())?]
+        }
+        _ => parse_bytes_pair_items(inner)?,
+    };
+    Ok(Argument {
+        name,
+        text,
+        items: Rc::new(items),
+    })
+}
+
+fn parse_identity(pair: Pair<Rule>) -> String {
+    pair.as_str().trim_start_matches('$').replace("::", ".")
+}
+
+fn parse_expansion_parts(
+    pair: Pair<Rule>,
+) -> Result<(String, Vec<Argument>), AnonymousEvaluationError> {
+    let mut inner_pairs = pair.into_inner();
+    let name: String = parse_identity(inner_pairs.next().unwrap());
+    let mut args: Vec<Argument> = Vec::new();
+    for argument in inner_pairs {
+        args.push(parse_argument(argument)?);
+    }
+    Ok((name, args))
+}
+
+fn parse_expansion(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationError> {
+    let (name, args): (String, Vec<Argument>) = parse_expansion_parts(pair)?;
     Ok(BytesItem::Expansion(name, args))
 }
 
@@ -87,6 +419,9 @@ fn parse_bytes_pair(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationEr
     match pair.as_rule() {
         Rule::number => parse_number(pair),
         Rule::string => parse_string(pair),
+        Rule::character => parse_character(pair),
+        Rule::base64 => parse_base64(pair),
+        Rule::typed => parse_typed(pair),
         Rule::left => Ok(BytesItem::Left),
         Rule::right => Ok(BytesItem::Right),
         Rule::expansion => parse_expansion(pair),
@@ -97,6 +432,26 @@ fn parse_bytes_pair(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationEr
     }
 }
 
+// A bare number that cannot be written as whole bytes, such as 8, is read as an integer
+fn parse_operand(pair: Pair<Rule>) -> Result<BytesItem, AnonymousEvaluationError> {
+    if pair.as_rule() == Rule::number {
+        let plain: bool = pair
+            .clone()
+            .into_inner()
+            .all(|inner| inner.as_rule() == Rule::signed);
+        let literal: String = strip_separators(pair.as_str());
+        if plain && decode_number(&literal, Sizing::Unsized).is_err() {
+            if let Ok(value) = big_integer_from_number(&literal) {
+                return Ok(BytesItem::Number(
+                    Number::Expression(Expression::Literal(value)),
+                    Sizing::Unsized,
+                ));
+            }
+        }
+    }
+    parse_bytes_pair(pair)
+}
+
 fn parse_bytes_pair_items(pair: Pair<Rule>) -> Result<Vec<BytesItem>, AnonymousEvaluationError> {
     match pair.as_rule() {
         Rule::items => {
@@ -114,8 +469,190 @@ fn parse_bytes_pair_items(pair: Pair<Rule>) -> Result<Vec<BytesItem>, AnonymousE
 
 pub fn parse_bytes(line: &str) -> Result<Vec<BytesItem>, AnonymousEvaluationError> {
     let pair: Pair<Rule> = BytesParser::parse(Rule::line, line)
-        .map_err(|e| AnonymousEvaluationError::new(format!("{}", e)))?
+        .map_err(|e| {
+            let row: usize = match e.line_col {
+                LineColLocation::Pos((row, _)) | LineColLocation::Span((row, _), _) => row,
+            };
+            AnonymousEvaluationError::new(format!("{}", e)).below(row - 1)
+        })?
         .next()
         .unwrap();
     parse_bytes_pair_items(pair)
 }
+
+fn parse_comparator(pair: Pair<Rule>) -> Result<Comparator, AnonymousEvaluationError> {
+    match pair.as_str() {
+        "==" => Ok(Comparator::Equal),
+        "!=" => Ok(Comparator::NotEqual),
+        "<" => Ok(Comparator::Less),
+        "<=" => Ok(Comparator::LessEqual),
+        ">" => Ok(Comparator::Greater),
+        ">=" => Ok(Comparator::GreaterEqual),
+        comparator => Err(AnonymousEvaluationError::new(format!(
+            "unexpected comparator {}",
+            comparator
+        ))),
+    }
+}
+
+fn parse_term(pair: Pair<Rule>) -> Result<Term, AnonymousEvaluationError> {
+    match pair.as_rule() {
+        Rule::length => Ok(Term::Length(parse_bytes_pair_items(
+            pair.into_inner().next().unwrap(),
+        )?)),
+        _ => Ok(Term::Item(parse_operand(pair)?)),
+    }
+}
+
+fn parse_condition_pair(pair: Pair<Rule>) -> Result<Condition, AnonymousEvaluationError> {
+    match pair.as_rule() {
+        Rule::disjunction | Rule::conjunction => {
+            let rule: Rule = pair.as_rule();
+            let mut conditions: Vec<Condition> = Vec::new();
+            for inner in pair.into_inner() {
+                conditions.push(parse_condition_pair(inner)?);
+            }
+            if conditions.len() == 1 {
+                Ok(conditions.pop().unwrap())
+            } else if rule == Rule::disjunction {
+                Ok(Condition::Any(conditions))
+            } else {
+                Ok(Condition::All(conditions))
+            }
+        }
+        Rule::negation => {
+            let mut inner_pairs = pair.into_inner();
+            let mut negations: usize = 0;
+            let mut inner: Pair<Rule> = inner_pairs.next().unwrap();
+            while inner.as_rule() == Rule::not {
+                negations += 1;
+                inner = inner_pairs.next().unwrap();
+            }
+            let mut condition: Condition = parse_condition_pair(inner)?;
+            for _ in 0..negations {
+                condition = Condition::Not(Box::new(condition));
+            }
+            Ok(condition)
+        }
+        Rule::defined => Ok(Condition::Defined(parse_identity(
+            pair.into_inner().next().unwrap(),
+        ))),
+        Rule::contains => {
+            let mut inner_pairs = pair.into_inner();
+            Ok(Condition::Contains(
+                parse_bytes_pair_items(inner_pairs.next().unwrap())?,
+                parse_bytes_pair_items(inner_pairs.next().unwrap())?,
+            ))
+        }
+        Rule::comparison => {
+            let mut inner_pairs = pair.into_inner();
+            let left: Term = parse_term(inner_pairs.next().unwrap())?;
+            match inner_pairs.next() {
+                None => Ok(Condition::Truthy(left)),
+                Some(comparator) => Ok(Condition::Compare(
+                    left,
+                    parse_comparator(comparator)?,
+                    parse_term(inner_pairs.next().unwrap())?,
+                )),
+            }
+        }
+        _ => Err(AnonymousEvaluationError::new(format!(
+            "unexpected syntax tree {:?}",
+            pair.as_rule()
+        ))),
+    }
+}
+
+pub fn parse_condition(line: &str) -> Result<Condition, AnonymousEvaluationError> {
+    let pair: Pair<Rule> = BytesParser::parse(Rule::condition, line)
+        .map_err(|e| AnonymousEvaluationError::new(format!("{}", e)))?
+        .next()
+        .unwrap();
+    parse_condition_pair(pair)
+}
+
+pub fn parse_iterable(line: &str) -> Result<Iterable, AnonymousEvaluationError> {
+    let pair: Pair<Rule> = BytesParser::parse(Rule::iteration, line)
+        .map_err(|e| AnonymousEvaluationError::new(format!("{}", e)))?
+        .next()
+        .unwrap();
+    match pair.as_rule() {
+        Rule::range => {
+            let mut inner_pairs = pair.into_inner();
+            Ok(Iterable::Range(
+                parse_expression(inner_pairs.next().unwrap())?,
+                parse_expression(inner_pairs.next().unwrap())?,
+            ))
+        }
+        Rule::expansion => Ok(Iterable::Expansion(parse_expansion(pair)?)),
+        _ => {
+            let mut elements: Vec<Vec<BytesItem>> = Vec::new();
+            for inner in pair.into_inner() {
+                elements.push(parse_bytes_pair_items(inner)?);
+            }
+            Ok(Iterable::List(elements))
+        }
+    }
+}
+
+// Splits definition parameters such as n=(0d8 + 1) fill=41 42 without parsing their defaults
+pub fn parse_parameters(line: &str) -> Result<Vec<String>, AnonymousEvaluationError> {
+    let pairs = BytesParser::parse(Rule::parameters, line)
+        .map_err(|e| AnonymousEvaluationError::new(format!("{}", e)))?;
+    Ok(pairs
+        .filter(|pair| pair.as_rule() == Rule::parameter)
+        .map(|pair| String::from(pair.as_str()))
+        .collect())
+}
+
+pub fn parse_count(line: &str) -> Result<Expression, AnonymousEvaluationError> {
+    let pair: Pair<Rule> = BytesParser::parse(Rule::count, line)
+        .map_err(|e| AnonymousEvaluationError::new(format!("{}", e)))?
+        .next()
+        .unwrap();
+    parse_expression(pair)
+}
+
+pub fn parse_padding(line: &str) -> Result<(Expression, Option<&str>), AnonymousEvaluationError> {
+    let mut pairs = BytesParser::parse(Rule::padding, line)
+        .map_err(|e| AnonymousEvaluationError::new(format!("{}", e)))?;
+    let amount: Expression = parse_expression(pairs.next().unwrap())?;
+    let fill: Option<&str> = pairs
+        .next()
+        .filter(|pair| pair.as_rule() == Rule::fill)
+        .map(|pair| pair.as_str());
+    Ok((amount, fill))
+}
+
+fn parse_message_pair(pair: Pair<Rule>) -> Result<String, AnonymousEvaluationError> {
+    let inner: Pair<Rule> = pair.into_inner().next().unwrap();
+    if inner.as_rule() == Rule::encoding {
+        return Err(AnonymousEvaluationError::new(
+            "messages must be plain strings without an encoding".to_string(),
+        ));
+    }
+    let result: Vec<u8> = decode_inner(&inner, &Charset::Utf8)?;
+    Ok(String::from_utf8_lossy(&result).into_owned())
+}
+
+pub fn parse_assertion(
+    line: &str,
+) -> Result<(Condition, Option<String>), AnonymousEvaluationError> {
+    let mut pairs = BytesParser::parse(Rule::assertion, line)
+        .map_err(|e| AnonymousEvaluationError::new(format!("{}", e)))?;
+    let condition: Condition = parse_condition_pair(pairs.next().unwrap())?;
+    match pairs.next() {
+        Some(pair) if pair.as_rule() == Rule::string => {
+            Ok((condition, Some(parse_message_pair(pair)?)))
+        }
+        _ => Ok((condition, None)),
+    }
+}
+
+pub fn parse_message(line: &str) -> Result<String, AnonymousEvaluationError> {
+    let pair: Pair<Rule> = BytesParser::parse(Rule::message, line)
+        .map_err(|e| AnonymousEvaluationError::new(format!("{}", e)))?
+        .next()
+        .unwrap();
+    parse_message_pair(pair)
+}
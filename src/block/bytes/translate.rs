@@ -1,4 +1,6 @@
 use crate::error::AnonymousEvaluationError;
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, Signed, ToPrimitive, Zero};
 
 pub fn byte_from_hexadecimal_digit(digit: char) -> Result<u8, AnonymousEvaluationError> {
     match digit {
@@ -11,15 +13,6 @@ pub fn byte_from_hexadecimal_digit(digit: char) -> Result<u8, AnonymousEvaluatio
     }
 }
 
-pub fn integer_from_hexadecimal(string: &str) -> Result<usize, AnonymousEvaluationError> {
-    let mut result: usize = 0;
-    for digit in string.chars() {
-        result <<= 4;
-        result += byte_from_hexadecimal_digit(digit)? as usize;
-    }
-    Ok(result)
-}
-
 pub fn bytes_from_hexadecimal(
     string: &str,
     strict: bool,
@@ -45,21 +38,15 @@ pub fn bytes_from_hexadecimal(
     Ok(result)
 }
 
-pub fn integer_from_decimal(string: &str) -> Result<usize, AnonymousEvaluationError> {
-    string
-        .parse::<usize>()
-        .map_err(|e| AnonymousEvaluationError::new(format!("invalid decimal format: {}", e)))
-}
-
 pub fn bytes_from_decimal(string: &str) -> Result<Vec<u8>, AnonymousEvaluationError> {
-    let mut result: Vec<u8> = Vec::new();
-    let mut work: usize = integer_from_decimal(string)?;
-    while work > 0 {
-        result.push((work % 256) as u8);
-        work = work / 256;
+    let work: BigUint = BigUint::parse_bytes(string.as_bytes(), 10).ok_or(
+        AnonymousEvaluationError::new("invalid decimal format".to_string()),
+    )?;
+    if work.is_zero() {
+        Ok(Vec::new())
+    } else {
+        Ok(work.to_bytes_be())
     }
-    result.reverse();
-    Ok(result)
 }
 
 pub fn byte_from_binary_digit(digit: char) -> Result<u8, AnonymousEvaluationError> {
@@ -71,15 +58,6 @@ pub fn byte_from_binary_digit(digit: char) -> Result<u8, AnonymousEvaluationErro
     }
 }
 
-pub fn integer_from_binary(string: &str) -> Result<usize, AnonymousEvaluationError> {
-    let mut result: usize = 0;
-    for digit in string.chars() {
-        result <<= 1;
-        result += byte_from_binary_digit(digit)? as usize;
-    }
-    Ok(result)
-}
-
 pub fn bytes_from_binary(string: &str, strict: bool) -> Result<Vec<u8>, AnonymousEvaluationError> {
     let mut result: Vec<u8> = Vec::new();
     let mut collected: Vec<char> = string.chars().collect();
@@ -107,6 +85,73 @@ pub fn bytes_from_binary(string: &str, strict: bool) -> Result<Vec<u8>, Anonymou
     Ok(result)
 }
 
+pub fn bytes_from_octal(string: &str) -> Result<Vec<u8>, AnonymousEvaluationError> {
+    let work: BigUint = BigUint::parse_bytes(string.as_bytes(), 8).ok_or(
+        AnonymousEvaluationError::new("invalid octal format".to_string()),
+    )?;
+    if work.is_zero() {
+        Ok(Vec::new())
+    } else {
+        Ok(work.to_bytes_be())
+    }
+}
+
+pub fn sextet_from_base64_digit(digit: char) -> Result<u32, AnonymousEvaluationError> {
+    match digit {
+        'A'..='Z' => Ok((digit as u32) - ('A' as u32)),
+        'a'..='z' => Ok((digit as u32) - ('a' as u32) + 26),
+        '0'..='9' => Ok((digit as u32) - ('0' as u32) + 52),
+        '+' => Ok(62),
+        '/' => Ok(63),
+        _ => Err(AnonymousEvaluationError::new(format!(
+            "invalid base64 digit {}",
+            digit
+        ))),
+    }
+}
+
+pub fn bytes_from_base64(string: &str) -> Result<Vec<u8>, AnonymousEvaluationError> {
+    let digits: &str = string.trim_end_matches('=');
+    let padding: usize = string.len() - digits.len();
+    if padding > 2 || (padding > 0 && !string.len().is_multiple_of(4)) || digits.len() % 4 == 1 {
+        return Err(AnonymousEvaluationError::new(
+            "invalid base64 length or padding".to_string(),
+        ));
+    }
+
+    let mut result: Vec<u8> = Vec::new();
+    let mut work: u32 = 0;
+    let mut bits: u32 = 0;
+    for digit in digits.chars() {
+        work = (work << 6) | sextet_from_base64_digit(digit)?;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((work >> bits) as u8);
+            work &= (1 << bits) - 1;
+        }
+    }
+    Ok(result)
+}
+
+pub fn is_float(string: &str) -> bool {
+    let magnitude: &str = string.trim_start_matches('-');
+    magnitude.starts_with("0f") && !magnitude[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+pub fn bytes_from_float(string: &str, size: usize) -> Result<Vec<u8>, AnonymousEvaluationError> {
+    let value: String = string.replacen("0f", "", 1);
+    let error = |e| AnonymousEvaluationError::new(format!("invalid float format: {}", e));
+    match size {
+        4 => Ok(value.parse::<f32>().map_err(error)?.to_be_bytes().to_vec()),
+        8 => Ok(value.parse::<f64>().map_err(error)?.to_be_bytes().to_vec()),
+        _ => Err(AnonymousEvaluationError::new(format!(
+            "float literals must be 4 or 8 bytes wide, not {}",
+            size
+        ))),
+    }
+}
+
 pub fn bytes_from_number(string: &str, strict: bool) -> Result<Vec<u8>, AnonymousEvaluationError> {
     if string.starts_with("0x") {
         bytes_from_hexadecimal(&string[2..], strict)
@@ -114,19 +159,283 @@ pub fn bytes_from_number(string: &str, strict: bool) -> Result<Vec<u8>, Anonymou
         bytes_from_decimal(&string[2..])
     } else if string.starts_with("0b") {
         bytes_from_binary(&string[2..], strict)
+    } else if string.starts_with("0o") {
+        bytes_from_octal(&string[2..])
     } else {
         bytes_from_hexadecimal(&string, strict)
     }
 }
 
-pub fn integer_from_number(string: &str) -> Result<usize, AnonymousEvaluationError> {
-    if string.starts_with("0x") {
-        integer_from_hexadecimal(&string[2..])
-    } else if string.starts_with("0d") {
-        integer_from_decimal(&string[2..])
-    } else if string.starts_with("0b") {
-        integer_from_binary(&string[2..])
+pub fn size_from_integer(value: &BigInt) -> Result<usize, AnonymousEvaluationError> {
+    value
+        .to_usize()
+        .ok_or(AnonymousEvaluationError::new(format!(
+            "invalid size {}",
+            value
+        )))
+}
+
+pub fn big_integer_from_number(string: &str) -> Result<BigInt, AnonymousEvaluationError> {
+    let (sign, magnitude): (Sign, &str) = match string.strip_prefix('-') {
+        Some(magnitude) => (Sign::Minus, magnitude),
+        None => (Sign::Plus, string),
+    };
+    let (radix, digits): (u32, &str) = if let Some(digits) = magnitude.strip_prefix("0x") {
+        (16, digits)
+    } else if let Some(digits) = magnitude.strip_prefix("0d") {
+        (10, digits)
+    } else if let Some(digits) = magnitude.strip_prefix("0b") {
+        (2, digits)
+    } else if let Some(digits) = magnitude.strip_prefix("0o") {
+        (8, digits)
     } else {
-        integer_from_hexadecimal(&string)
+        (16, magnitude)
+    };
+    let value: BigUint = BigUint::parse_bytes(digits.as_bytes(), radix).ok_or(
+        AnonymousEvaluationError::new(format!("invalid number format: {}", string)),
+    )?;
+    Ok(BigInt::from_biguint(sign, value))
+}
+
+pub struct IntegerType {
+    name: String,
+    signed: bool,
+    size: usize,
+    little_endian: bool,
+}
+
+impl IntegerType {
+    pub fn from_name(name: &str) -> Result<Self, AnonymousEvaluationError> {
+        let error = || AnonymousEvaluationError::new(format!("invalid integer type {}", name));
+        let signed: bool = match name.chars().next() {
+            Some('u') => false,
+            Some('i') => true,
+            _ => return Err(error()),
+        };
+        let (bits, little_endian): (&str, Option<bool>) =
+            if let Some(bits) = name[1..].strip_suffix("le") {
+                (bits, Some(true))
+            } else if let Some(bits) = name[1..].strip_suffix("be") {
+                (bits, Some(false))
+            } else {
+                (&name[1..], None)
+            };
+        let size: usize = match bits {
+            "8" => 1,
+            "16" => 2,
+            "32" => 4,
+            "64" => 8,
+            "128" => 16,
+            _ => return Err(error()),
+        };
+        let little_endian: bool = match little_endian {
+            Some(little_endian) => little_endian,
+            None if size == 1 => false,
+            None => {
+                return Err(AnonymousEvaluationError::new(format!(
+                    "integer type {} must specify le or be",
+                    name
+                )))
+            }
+        };
+        Ok(Self {
+            name: name.to_string(),
+            signed,
+            size,
+            little_endian,
+        })
     }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn encode(&self, value: &BigInt) -> Result<Vec<u8>, AnonymousEvaluationError> {
+        let bits: usize = self.size * 8;
+        let (minimum, maximum): (BigInt, BigInt) = if self.signed {
+            (-(BigInt::one() << (bits - 1)), BigInt::one() << (bits - 1))
+        } else {
+            (BigInt::zero(), BigInt::one() << bits)
+        };
+        if *value < minimum || *value >= maximum {
+            return Err(AnonymousEvaluationError::new(format!(
+                "value {} does not fit in {}",
+                value, self.name
+            )));
+        }
+
+        let unsigned: BigInt = if value.is_negative() {
+            value + (BigInt::one() << bits)
+        } else {
+            value.clone()
+        };
+        let mut result: Vec<u8> = unsigned.magnitude().to_bytes_be();
+        result.reverse();
+        result.resize(self.size, 0);
+        if !self.little_endian {
+            result.reverse();
+        }
+        Ok(result)
+    }
+}
+
+pub enum Charset {
+    Latin1,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Charset {
+    pub fn from_name(name: &str) -> Result<Self, AnonymousEvaluationError> {
+        match name {
+            "" | "latin1" => Ok(Charset::Latin1),
+            "u8" => Ok(Charset::Utf8),
+            "u16le" => Ok(Charset::Utf16Le),
+            "u16be" => Ok(Charset::Utf16Be),
+            _ => Err(AnonymousEvaluationError::new(format!(
+                "invalid string encoding {}",
+                name
+            ))),
+        }
+    }
+
+    pub fn can_encode(&self, character: char) -> bool {
+        match self {
+            Charset::Latin1 => (character as u32) <= 0xFF,
+            _ => true,
+        }
+    }
+
+    pub fn encode(&self, character: char, result: &mut Vec<u8>) {
+        match self {
+            Charset::Latin1 => result.push(character as u8),
+            Charset::Utf8 => {
+                let mut buffer: [u8; 4] = [0; 4];
+                result.extend(character.encode_utf8(&mut buffer).as_bytes());
+            }
+            Charset::Utf16Le | Charset::Utf16Be => {
+                let mut buffer: [u16; 2] = [0; 2];
+                for unit in character.encode_utf16(&mut buffer).iter() {
+                    match self {
+                        Charset::Utf16Le => result.extend(&unit.to_le_bytes()),
+                        _ => result.extend(&unit.to_be_bytes()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn resize_anchored_right(literal: &mut Vec<u8>, size: usize) {
+    literal.reverse();
+    literal.resize(size, 0);
+    literal.reverse();
+}
+
+fn resize_anchored_left(literal: &mut Vec<u8>, size: usize) {
+    literal.resize(size, 0);
+}
+
+// The two's complement of a big-endian magnitude, or None if it is below the minimum for its size
+fn negate(mut literal: Vec<u8>, size: usize) -> Option<Vec<u8>> {
+    let start: usize = literal
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(literal.len());
+    literal.drain(..start);
+    if literal.len() > size {
+        return None;
+    }
+    resize_anchored_right(&mut literal, size);
+    if let Some((first, rest)) = literal.split_first() {
+        if *first > 0x80 || (*first == 0x80 && rest.iter().any(|byte| *byte != 0)) {
+            return None;
+        }
+    }
+    let mut carry: bool = true;
+    for byte in literal.iter_mut().rev() {
+        let (sum, overflow) = (!*byte).overflowing_add(carry as u8);
+        *byte = sum;
+        carry = overflow;
+    }
+    Some(literal)
+}
+
+fn decode_negative(string: &str, size: usize) -> Result<Vec<u8>, AnonymousEvaluationError> {
+    negate(bytes_from_number(&string[1..], true)?, size).ok_or_else(|| {
+        AnonymousEvaluationError::new(format!(
+            "negative literal {} does not fit in {} bytes",
+            string, size
+        ))
+    })
+}
+
+pub enum Sizing<T> {
+    Unsized,
+    AnchoredRight(T),
+    AnchoredLeft(T),
+}
+
+pub fn decode_number(
+    literal: &str,
+    sizing: Sizing<usize>,
+) -> Result<Vec<u8>, AnonymousEvaluationError> {
+    if is_float(literal) {
+        match sizing {
+            Sizing::Unsized => bytes_from_float(literal, 8),
+            Sizing::AnchoredRight(size) | Sizing::AnchoredLeft(size) => {
+                bytes_from_float(literal, size)
+            }
+        }
+    } else if literal.starts_with('-') {
+        match sizing {
+            Sizing::Unsized => Err(AnonymousEvaluationError::new(format!(
+                "negative literal {} requires an explicit size",
+                literal
+            ))),
+            Sizing::AnchoredRight(size) | Sizing::AnchoredLeft(size) => {
+                decode_negative(literal, size)
+            }
+        }
+    } else {
+        let mut result: Vec<u8> = bytes_from_number(literal, true)?;
+        match sizing {
+            Sizing::Unsized => {}
+            Sizing::AnchoredRight(size) => resize_anchored_right(&mut result, size),
+            Sizing::AnchoredLeft(size) => resize_anchored_left(&mut result, size),
+        }
+        Ok(result)
+    }
+}
+
+pub fn encode_integer(
+    value: &BigInt,
+    sizing: Sizing<usize>,
+) -> Result<Vec<u8>, AnonymousEvaluationError> {
+    let mut result: Vec<u8> = if value.is_zero() {
+        Vec::new()
+    } else {
+        value.magnitude().to_bytes_be()
+    };
+    match sizing {
+        Sizing::Unsized if value.is_negative() => {
+            return Err(AnonymousEvaluationError::new(format!(
+                "negative value {} requires an explicit size",
+                value
+            )))
+        }
+        Sizing::Unsized => {}
+        Sizing::AnchoredRight(size) | Sizing::AnchoredLeft(size) if value.is_negative() => {
+            result = negate(result, size).ok_or_else(|| {
+                AnonymousEvaluationError::new(format!(
+                    "negative value {} does not fit in {} bytes",
+                    value, size
+                ))
+            })?;
+        }
+        Sizing::AnchoredRight(size) => resize_anchored_right(&mut result, size),
+        Sizing::AnchoredLeft(size) => resize_anchored_left(&mut result, size),
+    }
+    Ok(result)
 }
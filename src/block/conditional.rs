@@ -0,0 +1,94 @@
+use crate::block::bytes::condition::Condition;
+use crate::block::Block;
+use crate::error::{AnonymousEvaluationErrorResult, EvaluationError};
+use crate::evaluator::scope::EvaluatorScope;
+use std::rc::Rc;
+
+struct Branch {
+    line_number: usize,
+    condition: Option<Condition>,
+    blocks: Vec<Rc<dyn Block>>,
+}
+
+pub struct IfBlock {
+    branches: Vec<Branch>,
+}
+
+impl IfBlock {
+    pub fn new(
+        line_number: usize,
+        condition: &str,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<Self, EvaluationError> {
+        Ok(Self {
+            branches: vec![Branch {
+                line_number,
+                condition: Some(Condition::new(condition).map_err_at(line_number)?),
+                blocks,
+            }],
+        })
+    }
+
+    pub fn add_elif(
+        &mut self,
+        line_number: usize,
+        condition: &str,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<(), EvaluationError> {
+        self.add_branch(
+            line_number,
+            Some(Condition::new(condition).map_err_at(line_number)?),
+            blocks,
+        )
+    }
+
+    pub fn add_else(
+        &mut self,
+        line_number: usize,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<(), EvaluationError> {
+        self.add_branch(line_number, None, blocks)
+    }
+
+    fn add_branch(
+        &mut self,
+        line_number: usize,
+        condition: Option<Condition>,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<(), EvaluationError> {
+        if self.branches.last().unwrap().condition.is_none() {
+            return Err(EvaluationError::new(
+                line_number,
+                "unexpected branch after @else".to_string(),
+            ));
+        }
+        self.branches.push(Branch {
+            line_number,
+            condition,
+            blocks,
+        });
+        Ok(())
+    }
+}
+
+impl Block for IfBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        for branch in self.branches.iter() {
+            let taken: bool = match &branch.condition {
+                Some(condition) => condition.evaluate(scope).map_err_at(branch.line_number)?,
+                None => true,
+            };
+            if taken {
+                let mut result: Vec<u8> = Vec::new();
+                let start: usize = scope.layout().position();
+                for block in branch.blocks.iter() {
+                    scope.layout().seek(start + result.len());
+                    result.extend(block.evaluate(scope)?);
+                }
+                scope.layout().seek(start);
+                return Ok(result);
+            }
+        }
+        Ok(Vec::new())
+    }
+}
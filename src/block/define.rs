@@ -1,50 +1,87 @@
 mod expansion;
+mod parameter;
 
+use crate::block::bytes::parse_parameters;
 use crate::block::define::expansion::DefineExpansion;
+use crate::block::define::parameter::Parameter;
 use crate::block::{Block, MacroBlock};
-use crate::error::EvaluationError;
+use crate::error::{AnonymousEvaluationErrorResult, EvaluationError};
 use crate::evaluator::scope::EvaluatorScope;
 use std::rc::Rc;
 
 pub struct DefineBlock {
     line_number: usize,
     name: String,
-    parameters: Vec<String>,
+    parameters: Vec<Parameter>,
     blocks: Vec<Rc<dyn Block>>,
+    lexical: bool,
 }
 
 impl Block for DefineBlock {
     fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
-        scope.set(
-            &self.name,
-            Box::new(DefineExpansion::new(
+        let expansion: DefineExpansion = if self.lexical {
+            DefineExpansion::lexical(
                 self.name.clone(),
                 self.parameters.clone(),
                 self.blocks.clone(),
-            )),
-        );
+                scope.capture(),
+            )
+        } else {
+            DefineExpansion::new(
+                self.name.clone(),
+                self.parameters.clone(),
+                self.blocks.clone(),
+            )
+        };
+        scope.set(&self.name, Box::new(expansion));
         Ok(Vec::new())
     }
 }
 
-impl MacroBlock for DefineBlock {
-    fn allocate(
+impl DefineBlock {
+    pub fn with_scoping(
         line_number: usize,
-        mut args: Vec<String>,
+        header: &str,
         blocks: Vec<Rc<dyn Block>>,
+        lexical: bool,
     ) -> Result<Rc<Self>, EvaluationError> {
-        if args.len() < 1 {
+        let header: &str = header.trim();
+        let (name, rest): (&str, &str) =
+            header.split_at(header.find(char::is_whitespace).unwrap_or(header.len()));
+        if name.is_empty() || name.starts_with('#') {
             Err(EvaluationError::new(
                 line_number,
                 "expected at least one argument indicating definition name".to_string(),
             ))
         } else {
+            let name: String = String::from(name);
+            let mut parameters: Vec<Parameter> = Vec::new();
+            for arg in parse_parameters(rest).map_err_at(line_number)?.iter() {
+                if parameters.last().is_some_and(|p: &Parameter| p.variadic) {
+                    return Err(EvaluationError::new(
+                        line_number,
+                        format!("variadic parameter must be last, found {}", arg),
+                    ));
+                }
+                parameters.push(Parameter::new(line_number, arg)?);
+            }
             Ok(Rc::new(Self {
                 line_number,
-                name: args.remove(0),
-                parameters: args,
+                name,
+                parameters,
                 blocks,
+                lexical,
             }))
         }
     }
 }
+
+impl MacroBlock for DefineBlock {
+    fn allocate(
+        line_number: usize,
+        args: Vec<String>,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        Self::with_scoping(line_number, &args.join(" "), blocks, false)
+    }
+}
@@ -1,52 +1,171 @@
+use crate::block::define::parameter::Parameter;
 use crate::block::Block;
 use crate::error::AnonymousEvaluationError;
-use crate::evaluator::evaluate;
-use crate::evaluator::expansion::{Expansion, InlineExpansion};
-use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::evaluate_value;
+use crate::evaluator::expansion::{Arguments, Expansion, InlineExpansion, Thunk};
+use crate::evaluator::scope::{Boundary, Environment, EvaluatorScope, Expansions};
+use crate::evaluator::value::Value;
 use std::rc::Rc;
 
+#[derive(Clone)]
 pub struct DefineExpansion {
     name: String,
-    parameters: Vec<String>,
+    parameters: Vec<Parameter>,
     blocks: Vec<Rc<dyn Block>>,
+    environment: Option<(Rc<Environment>, Rc<Boundary>)>,
 }
 
 impl DefineExpansion {
-    pub fn new(name: String, parameters: Vec<String>, blocks: Vec<Rc<dyn Block>>) -> Self {
+    pub fn new(name: String, parameters: Vec<Parameter>, blocks: Vec<Rc<dyn Block>>) -> Self {
         Self {
             name,
             parameters,
             blocks,
+            environment: None,
         }
     }
+
+    pub fn lexical(
+        name: String,
+        parameters: Vec<Parameter>,
+        blocks: Vec<Rc<dyn Block>>,
+        environment: Rc<Environment>,
+    ) -> Self {
+        Self {
+            environment: Some((environment, Boundary::new(name.clone()))),
+            ..Self::new(name, parameters, blocks)
+        }
+    }
+
+    fn signature(&self) -> String {
+        let parameters: Vec<String> = self.parameters.iter().map(|p| p.to_string()).collect();
+        format!("${}({})", self.name, parameters.join(", "))
+    }
+
+    fn bind(
+        &self,
+        inner: &mut EvaluatorScope,
+        args: &Arguments,
+    ) -> Result<(), AnonymousEvaluationError> {
+        let variadic: Option<&Parameter> = self.parameters.last().filter(|p| p.variadic);
+        let fixed: &[Parameter] =
+            &self.parameters[..self.parameters.len() - variadic.iter().count()];
+        if variadic.is_none() && args.positional.len() > fixed.len() {
+            return Err(AnonymousEvaluationError::new(format!(
+                "expansion {} expected at most {} args, got {}",
+                self.signature(),
+                fixed.len(),
+                args.positional.len()
+            )));
+        }
+
+        let mut thunks: Vec<Option<&dyn Thunk>> = vec![None; fixed.len()];
+        for (index, thunk) in args.positional.iter().take(fixed.len()).enumerate() {
+            thunks[index] = Some(*thunk);
+        }
+        for (name, thunk) in args.named.iter() {
+            match fixed.iter().position(|p| p.name == *name) {
+                Some(index) if thunks[index].is_none() => thunks[index] = Some(*thunk),
+                Some(_) => {
+                    return Err(AnonymousEvaluationError::new(format!(
+                        "expansion {} got multiple values for {}",
+                        self.signature(),
+                        name
+                    )))
+                }
+                None => {
+                    return Err(AnonymousEvaluationError::new(format!(
+                        "expansion {} has no parameter named {}",
+                        self.signature(),
+                        name
+                    )))
+                }
+            }
+        }
+
+        for (parameter, thunk) in fixed.iter().zip(thunks) {
+            let value: Value = match (thunk, &parameter.default) {
+                (Some(thunk), _) if parameter.lazy => {
+                    inner.set(&parameter.name, thunk.delay(args.scope));
+                    continue;
+                }
+                (Some(thunk), _) => thunk.force(args.scope)?,
+                (None, Some((_, block))) => block.evaluate_value(inner).map_err(|e| {
+                    AnonymousEvaluationError::new(format!(
+                        "error while evaluating default for {}:\n{}",
+                        parameter.name, e
+                    ))
+                })?,
+                (None, None) => {
+                    return Err(AnonymousEvaluationError::new(format!(
+                        "expansion {} missing argument {}",
+                        self.signature(),
+                        parameter.name
+                    )))
+                }
+            };
+            inner.set(
+                &parameter.name,
+                InlineExpansion::new(parameter.name.clone(), value),
+            );
+        }
+        if let Some(parameter) = variadic {
+            let mut rest: Vec<Value> = Vec::new();
+            for thunk in args.positional.iter().skip(fixed.len()) {
+                rest.push(thunk.force(args.scope)?);
+            }
+            inner.set(
+                &parameter.name,
+                InlineExpansion::new(parameter.name.clone(), Value::List(rest)),
+            );
+        }
+        Ok(())
+    }
 }
 
 impl Expansion for DefineExpansion {
     fn expand(
         &self,
         scope: &EvaluatorScope,
-        args: &Vec<Vec<u8>>,
-    ) -> Result<Vec<u8>, AnonymousEvaluationError> {
-        let mut inner: EvaluatorScope = EvaluatorScope::child(scope);
-
-        if self.parameters.len() == 1 && args.len() == 0 {
-            let name: &String = self.parameters.first().unwrap();
-            inner.set(name, InlineExpansion::new(name.clone(), Vec::new()));
-        } else if self.parameters.len() != args.len() {
-            return Err(AnonymousEvaluationError::new(format!(
-                "expansion ${} expected {} args, got {}",
-                self.name,
-                self.parameters.len(),
-                args.len()
-            )))
-        } else {
-            for (name, value) in self.parameters.iter().zip(args) {
-                inner.set(&name, InlineExpansion::new(name.clone(), value.clone()));
+        args: &Arguments,
+    ) -> Result<Value, AnonymousEvaluationError> {
+        let lexical: EvaluatorScope;
+        let parent: &EvaluatorScope = match &self.environment {
+            Some((environment, boundary)) => {
+                let mut expansions: Expansions = Expansions::new();
+                expansions.insert(self.name.clone(), Rc::new(self.clone()));
+                lexical = EvaluatorScope::lexical(
+                    scope,
+                    expansions,
+                    environment.clone(),
+                    boundary.clone(),
+                );
+                &lexical
             }
+            None => scope,
         };
+        let mut inner: EvaluatorScope = EvaluatorScope::child(parent);
+        inner.enter(&self.name)?;
+        inner.open_labels();
+
+        match self.parameters.as_slice() {
+            [parameter]
+                if args.is_empty() && parameter.default.is_none() && !parameter.variadic =>
+            {
+                inner.set(
+                    &parameter.name,
+                    InlineExpansion::new(parameter.name.clone(), Value::Bytes(Vec::new())),
+                );
+            }
+            _ => self.bind(&mut inner, args)?,
+        }
 
-        let result: Vec<u8> = evaluate(&self.blocks, &inner).map_err(|e| {
-            AnonymousEvaluationError::new(format!("error while expanding definition:\n{}", e))
+        let result: Value = evaluate_value(&self.blocks, &inner).map_err(|e| {
+            if e.is_recursion() {
+                AnonymousEvaluationError::propagate(e)
+            } else {
+                AnonymousEvaluationError::new(format!("error while expanding definition:\n{}", e))
+            }
         })?;
         Ok(result)
     }
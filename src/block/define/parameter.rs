@@ -0,0 +1,63 @@
+use crate::block::bytes::BytesBlock;
+use crate::block::Block;
+use crate::error::EvaluationError;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Clone)]
+pub struct Parameter {
+    pub name: String,
+    pub default: Option<(String, Rc<dyn Block>)>,
+    pub variadic: bool,
+    pub lazy: bool,
+}
+
+impl Parameter {
+    pub fn new(line_number: usize, token: &str) -> Result<Self, EvaluationError> {
+        let (token, lazy): (&str, bool) = match token.strip_prefix('&') {
+            Some(token) => (token, true),
+            None => (token, false),
+        };
+        let (name, default, variadic) = match token.find('=') {
+            Some(index) => {
+                let text: String = String::from(&token[index + 1..]);
+                let block: Rc<dyn Block> = Rc::new(BytesBlock::new(line_number, text.clone())?);
+                (&token[..index], Some((text, block)), false)
+            }
+            None => match token.strip_suffix("...") {
+                Some(name) => (name, None, true),
+                None => (token, None, false),
+            },
+        };
+        if name.is_empty() {
+            return Err(EvaluationError::new(
+                line_number,
+                format!("invalid parameter {}", token),
+            ));
+        } else if lazy && variadic {
+            return Err(EvaluationError::new(
+                line_number,
+                format!("variadic parameter {} cannot be lazy", name),
+            ));
+        }
+        Ok(Self {
+            name: String::from(name),
+            default,
+            variadic,
+            lazy,
+        })
+    }
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.lazy {
+            write!(f, "&")?;
+        }
+        match &self.default {
+            Some((text, _)) => write!(f, "{}={}", self.name, text),
+            None if self.variadic => write!(f, "{}...", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
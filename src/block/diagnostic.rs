@@ -0,0 +1,70 @@
+use crate::block::bytes::condition::Condition;
+use crate::block::bytes::{parse_message, BytesBlock};
+use crate::block::Block;
+use crate::error::{AnonymousEvaluationErrorResult, EvaluationError};
+use crate::evaluator::scope::EvaluatorScope;
+
+enum Diagnostic {
+    Assert(Box<Condition>, Option<String>),
+    Error(String),
+    Warn(String),
+    Print(BytesBlock),
+}
+
+pub struct DiagnosticBlock {
+    line_number: usize,
+    diagnostic: Diagnostic,
+}
+
+impl DiagnosticBlock {
+    pub fn new(line_number: usize, macro_name: &str, text: &str) -> Result<Self, EvaluationError> {
+        let diagnostic: Diagnostic = match macro_name {
+            "@assert" => {
+                let (condition, message) = Condition::with_message(text).map_err_at(line_number)?;
+                Diagnostic::Assert(Box::new(condition), message)
+            }
+            "@error" => Diagnostic::Error(parse_message(text).map_err_at(line_number)?),
+            "@warn" => Diagnostic::Warn(parse_message(text).map_err_at(line_number)?),
+            _ => Diagnostic::Print(BytesBlock::new(line_number, String::from(text))?),
+        };
+        Ok(Self {
+            line_number,
+            diagnostic,
+        })
+    }
+}
+
+impl Block for DiagnosticBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        match &self.diagnostic {
+            Diagnostic::Assert(condition, message) => {
+                if !condition.evaluate(scope).map_err_at(self.line_number)? {
+                    return Err(EvaluationError::new(
+                        self.line_number,
+                        match message {
+                            Some(message) => format!("assertion failed: {}", message),
+                            None => "assertion failed".to_string(),
+                        },
+                    ));
+                }
+            }
+            Diagnostic::Error(message) => {
+                return Err(EvaluationError::new(self.line_number, message.clone()))
+            }
+            Diagnostic::Warn(message) => scope
+                .layout()
+                .report(format!("Warning on line {}: {}", self.line_number, message)),
+            Diagnostic::Print(block) => {
+                let bytes: Vec<String> = block
+                    .evaluate(scope)?
+                    .iter()
+                    .map(|byte| format!("{:02X}", byte))
+                    .collect();
+                scope
+                    .layout()
+                    .report(format!("Line {}: {}", self.line_number, bytes.join(" ")));
+            }
+        }
+        Ok(Vec::new())
+    }
+}
@@ -0,0 +1,124 @@
+use super::{Block, RawMacroBlock};
+use crate::block::bytes::translate::bytes_from_hexadecimal;
+use crate::error::{AnonymousEvaluationErrorResult, EvaluationError};
+use crate::evaluator::scope::EvaluatorScope;
+use std::rc::Rc;
+
+pub struct HexBlock {
+    bytes: Vec<u8>,
+}
+
+impl Block for HexBlock {
+    fn evaluate(&self, _: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        Ok(self.bytes.clone())
+    }
+}
+
+enum DumpLine<'a> {
+    // Hex words, at an offset if the line came from a dump
+    Hex(Option<usize>, &'a str),
+    // hexdump -C abbreviates a run of identical lines as a lone asterisk
+    Repeat,
+    // hexdump -C ends with the total length on its own, which is otherwise just hex
+    Length(usize, &'a str),
+}
+
+fn split_offset<'a>(line: &'a str, separator: &str) -> Option<(usize, &'a str)> {
+    let line: &str = line.trim_start();
+    let length: usize = line
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(line.len());
+    let rest: &str = line[length..].strip_prefix(separator)?;
+    Some((usize::from_str_radix(&line[..length], 16).ok()?, rest))
+}
+
+// Strips the offset column and ASCII gutter from lines of xxd or hexdump -C output
+fn classify(line: &str, canonical: bool) -> (DumpLine<'_>, bool) {
+    let trimmed: &str = line.trim();
+    if canonical && trimmed == "*" {
+        return (DumpLine::Repeat, true);
+    }
+    // 00000000: 4865 6c6c 6f0a  Hello.
+    if let Some((offset, rest)) = split_offset(line, ":") {
+        let rest: &str = rest.strip_prefix(' ').unwrap_or(rest);
+        let end: usize = rest.find("  ").unwrap_or(rest.len());
+        return (DumpLine::Hex(Some(offset), &rest[..end]), canonical);
+    }
+    // 00000000  48 65 6c 6c 6f 0a  |Hello.|
+    if trimmed.ends_with('|') {
+        if let Some((offset, rest)) = split_offset(line, "  ") {
+            if let Some(end) = rest.find('|').filter(|end| rest.len() - 1 > *end) {
+                return (DumpLine::Hex(Some(offset), &rest[..end]), true);
+            }
+        }
+    }
+    if canonical {
+        if let Some((offset, "")) = split_offset(trimmed, "") {
+            return (DumpLine::Length(offset, line), true);
+        }
+    }
+    (
+        DumpLine::Hex(None, line.split('#').next().unwrap()),
+        canonical,
+    )
+}
+
+impl RawMacroBlock for HexBlock {
+    fn allocate(
+        line_number: usize,
+        args: Vec<String>,
+        lines: Vec<String>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        if !args.is_empty() {
+            return Err(EvaluationError::new(
+                line_number,
+                "hex blocks do not take arguments".to_string(),
+            ));
+        }
+
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut canonical: bool = false;
+        let mut origin: Option<usize> = None;
+        let mut previous: Vec<u8> = Vec::new();
+        let mut repeating: bool = false;
+        for (i, line) in lines.iter().enumerate() {
+            let (dump_line, dump): (DumpLine, bool) = classify(line, canonical);
+            canonical = dump;
+            let (offset, content): (Option<usize>, &str) = match dump_line {
+                DumpLine::Repeat => {
+                    repeating = true;
+                    continue;
+                }
+                DumpLine::Hex(offset, content) => (offset, content),
+                // A bare offset ends a repeat or matches the bytes so far, and is hex otherwise
+                DumpLine::Length(offset, _)
+                    if repeating || origin.map(|origin| origin + bytes.len()) == Some(offset) =>
+                {
+                    (Some(offset), "")
+                }
+                DumpLine::Length(_, line) => (None, line.split('#').next().unwrap()),
+            };
+            if let Some(offset) = offset {
+                // Dumps of part of a file start at the offset of that part
+                let offset: usize = offset.saturating_sub(*origin.get_or_insert(offset));
+                while repeating && !previous.is_empty() && bytes.len() < offset {
+                    bytes.extend(&previous);
+                }
+                if repeating && bytes.len() != offset {
+                    return Err(EvaluationError::new(
+                        line_number + 1 + i,
+                        format!("offset {:#x} does not follow the repeated line", offset),
+                    ));
+                }
+                repeating = false;
+            }
+            let start: usize = bytes.len();
+            for word in content.split(|c: char| c.is_whitespace() || c == ',') {
+                let digits: &str = word.trim_start_matches("0x").trim_start_matches("\\x");
+                bytes.extend(bytes_from_hexadecimal(digits, true).map_err_at(line_number + 1 + i)?);
+            }
+            previous = bytes[start..].to_vec();
+        }
+        Ok(Rc::new(Self { bytes }))
+    }
+}
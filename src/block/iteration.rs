@@ -0,0 +1,59 @@
+use crate::block::bytes::iterable::Iterable;
+use crate::block::Block;
+use crate::error::{AnonymousEvaluationErrorResult, EvaluationError};
+use crate::evaluator::expansion::InlineExpansion;
+use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
+use std::rc::Rc;
+
+pub struct ForBlock {
+    line_number: usize,
+    name: String,
+    iterable: Iterable,
+    blocks: Vec<Rc<dyn Block>>,
+}
+
+impl ForBlock {
+    pub fn new(
+        line_number: usize,
+        header: &str,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<Self, EvaluationError> {
+        let header: &str = header.trim_start();
+        let (name, rest): (&str, &str) =
+            header.split_at(header.find(char::is_whitespace).unwrap_or(header.len()));
+        let rest: &str = rest.trim_start();
+        if name.is_empty() || !rest.starts_with("in") || !rest[2..].starts_with(char::is_whitespace)
+        {
+            return Err(EvaluationError::new(
+                line_number,
+                "expected a loop of the form @for name in iterable".to_string(),
+            ));
+        }
+        Ok(Self {
+            line_number,
+            name: String::from(name),
+            iterable: Iterable::new(&rest[2..]).map_err_at(line_number)?,
+            blocks,
+        })
+    }
+}
+
+impl Block for ForBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        let mut result: Vec<u8> = Vec::new();
+        let values: Vec<Value> = self.iterable.values(scope).map_err_at(self.line_number)?;
+        let start: usize = scope.layout().position();
+        for value in values {
+            let mut inner = EvaluatorScope::child(scope);
+            inner.open_labels();
+            inner.set(&self.name, InlineExpansion::new(self.name.clone(), value));
+            for block in self.blocks.iter() {
+                inner.layout().seek(start + result.len());
+                result.extend(block.evaluate(&mut inner)?);
+            }
+        }
+        scope.layout().seek(start);
+        Ok(result)
+    }
+}
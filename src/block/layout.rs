@@ -0,0 +1,208 @@
+use crate::block::bytes::expression::Expression;
+use crate::block::bytes::{parse_padding, BytesBlock};
+use crate::block::{Block, MacroBlock};
+use crate::error::{AnonymousEvaluationErrorResult, EvaluationError};
+use crate::evaluator::scope::EvaluatorScope;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+use std::rc::Rc;
+
+fn expect_empty(
+    line_number: usize,
+    macro_name: &str,
+    blocks: &[Rc<dyn Block>],
+) -> Result<(), EvaluationError> {
+    if blocks.is_empty() {
+        Ok(())
+    } else {
+        Err(EvaluationError::new(
+            line_number,
+            format!("unexpected indented block after {}", macro_name),
+        ))
+    }
+}
+
+pub struct LabelBlock {
+    line_number: usize,
+    name: String,
+}
+
+impl Block for LabelBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        scope
+            .define_label(&self.name, self.line_number)
+            .map_err_at(self.line_number)?;
+        Ok(Vec::new())
+    }
+}
+
+impl MacroBlock for LabelBlock {
+    fn allocate(
+        line_number: usize,
+        mut args: Vec<String>,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        expect_empty(line_number, "@label", &blocks)?;
+        if args.len() == 1 {
+            Ok(Rc::new(Self {
+                line_number,
+                name: args.remove(0),
+            }))
+        } else {
+            Err(EvaluationError::new(
+                line_number,
+                "expected exactly one argument indicating label name".to_string(),
+            ))
+        }
+    }
+}
+
+pub struct BaseBlock {
+    line_number: usize,
+    address: Expression,
+}
+
+impl Block for BaseBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        let address: BigInt = self.address.evaluate(scope).map_err_at(self.line_number)?;
+        if address.is_negative() {
+            return Err(EvaluationError::new(
+                self.line_number,
+                format!("invalid base address {}", address),
+            ));
+        }
+        scope.layout().set_base(address, self.line_number);
+        Ok(Vec::new())
+    }
+}
+
+impl MacroBlock for BaseBlock {
+    fn allocate(
+        line_number: usize,
+        args: Vec<String>,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        expect_empty(line_number, "@base", &blocks)?;
+        if !args.is_empty() {
+            Ok(Rc::new(Self {
+                line_number,
+                address: Expression::new(&args.join(" ")).map_err_at(line_number)?,
+            }))
+        } else {
+            Err(EvaluationError::new(
+                line_number,
+                "expected an expression indicating base address".to_string(),
+            ))
+        }
+    }
+}
+
+// Padding is built in memory, so anything larger is almost certainly a mistaken target
+const MAX_PADDING: usize = 0x1000_0000;
+
+enum Padding {
+    Align,
+    PadTo,
+    Org,
+}
+
+pub struct PaddingBlock {
+    line_number: usize,
+    padding: Padding,
+    amount: Expression,
+    fill: Option<BytesBlock>,
+}
+
+impl PaddingBlock {
+    pub fn new(line_number: usize, macro_name: &str, text: &str) -> Result<Self, EvaluationError> {
+        let text: &str = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            return Err(EvaluationError::new(
+                line_number,
+                format!("expected an expression after {}", macro_name),
+            ));
+        }
+        let (amount, fill): (Expression, Option<&str>) =
+            parse_padding(text).map_err_at(line_number)?;
+        Ok(Self {
+            line_number,
+            padding: match macro_name {
+                "@align" => Padding::Align,
+                "@pad_to" => Padding::PadTo,
+                _ => Padding::Org,
+            },
+            amount,
+            fill: match fill {
+                Some(fill) => Some(BytesBlock::new(line_number, String::from(fill))?),
+                None => None,
+            },
+        })
+    }
+
+    // The number of bytes between the current position and the target
+    fn length(&self, scope: &EvaluatorScope) -> Result<usize, EvaluationError> {
+        let amount: BigInt = self.amount.evaluate(scope).map_err_at(self.line_number)?;
+        let layout = scope.layout();
+        let position: usize = layout.position();
+        let target: Option<usize> = match self.padding {
+            Padding::Align => amount
+                .to_usize()
+                .filter(|alignment| *alignment > 0)
+                .map(|alignment| position.div_ceil(alignment) * alignment),
+            Padding::PadTo => amount.to_usize(),
+            Padding::Org => (&amount - layout.base()).to_usize(),
+        };
+        match target {
+            Some(target) if target >= position && target - position > MAX_PADDING => {
+                Err(EvaluationError::new(
+                    self.line_number,
+                    format!(
+                        "padding of {:#x} bytes exceeds the limit of {:#x} bytes",
+                        target - position,
+                        MAX_PADDING
+                    ),
+                ))
+            }
+            Some(target) if target >= position => Ok(target - position),
+            Some(_) => Err(EvaluationError::new(
+                self.line_number,
+                match self.padding {
+                    Padding::Org => format!(
+                        "cannot move back to address {:#x}, already at {:#x}",
+                        amount,
+                        layout.base() + position
+                    ),
+                    _ => format!(
+                        "cannot pad to offset {}, already at offset {}",
+                        amount, position
+                    ),
+                },
+            )),
+            None => Err(EvaluationError::new(
+                self.line_number,
+                match self.padding {
+                    Padding::Align => format!("invalid alignment {}", amount),
+                    Padding::PadTo => format!("invalid offset {}", amount),
+                    Padding::Org => format!("address {:#x} is below the base address", amount),
+                },
+            )),
+        }
+    }
+}
+
+impl Block for PaddingBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        let length: usize = self.length(scope)?;
+        let fill: Vec<u8> = match &self.fill {
+            Some(fill) => fill.evaluate(scope)?,
+            None => vec![0],
+        };
+        if fill.is_empty() {
+            return Err(EvaluationError::new(
+                self.line_number,
+                "padding fill must not be empty".to_string(),
+            ));
+        }
+        Ok(fill.iter().copied().cycle().take(length).collect())
+    }
+}
@@ -0,0 +1,137 @@
+use crate::block::{Block, MacroBlock};
+use crate::error::EvaluationError;
+use crate::evaluator::module::{Module, ModuleExpansion};
+use crate::evaluator::scope::{EvaluatorScope, Expansions};
+use std::rc::Rc;
+
+pub struct ModuleBlock {
+    line_number: usize,
+    namespace: String,
+    isolated: bool,
+    blocks: Vec<Rc<dyn Block>>,
+}
+
+impl ModuleBlock {
+    pub fn import(line_number: usize, namespace: String, blocks: Vec<Rc<dyn Block>>) -> Self {
+        Self {
+            line_number,
+            namespace,
+            isolated: true,
+            blocks,
+        }
+    }
+
+    fn export(
+        &self,
+        scope: &mut EvaluatorScope,
+        expansions: Expansions,
+        exports: Vec<String>,
+    ) -> Result<(), EvaluationError> {
+        for name in exports.iter() {
+            if !expansions.contains_key(name) {
+                return Err(EvaluationError::new(
+                    self.line_number,
+                    format!(
+                        "namespace {} exports undefined name {}",
+                        self.namespace, name
+                    ),
+                ));
+            }
+        }
+
+        let module: Module = Rc::new(expansions);
+        for name in module.keys() {
+            if exports.is_empty() || exports.contains(name) {
+                scope.set(
+                    &format!("{}.{}", self.namespace, name),
+                    ModuleExpansion::new(module.clone(), name.clone()),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Block for ModuleBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        let root: EvaluatorScope = scope.detached(None);
+        let mut inner: EvaluatorScope = if self.isolated {
+            EvaluatorScope::child(&root)
+        } else {
+            EvaluatorScope::child(scope)
+        };
+        for block in self.blocks.iter() {
+            if !block.evaluate(&mut inner)?.is_empty() {
+                return Err(EvaluationError::new(
+                    self.line_number,
+                    format!("namespace {} may only contain definitions", self.namespace),
+                ));
+            }
+        }
+        let (expansions, exports) = inner.into_parts();
+        self.export(scope, expansions, exports)?;
+        Ok(Vec::new())
+    }
+}
+
+impl MacroBlock for ModuleBlock {
+    fn allocate(
+        line_number: usize,
+        mut args: Vec<String>,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        if args.len() == 1 {
+            Ok(Rc::new(Self {
+                line_number,
+                namespace: args.remove(0),
+                isolated: false,
+                blocks,
+            }))
+        } else {
+            Err(EvaluationError::new(
+                line_number,
+                "expected exactly one argument indicating namespace name".to_string(),
+            ))
+        }
+    }
+}
+
+pub struct ExportBlock {
+    names: Vec<String>,
+}
+
+impl Block for ExportBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        for name in self.names.iter() {
+            scope.export(name);
+        }
+        Ok(Vec::new())
+    }
+}
+
+impl MacroBlock for ExportBlock {
+    fn allocate(
+        line_number: usize,
+        args: Vec<String>,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        if args.is_empty() {
+            Err(EvaluationError::new(
+                line_number,
+                "expected at least one argument indicating exported name".to_string(),
+            ))
+        } else if !blocks.is_empty() {
+            Err(EvaluationError::new(
+                line_number,
+                "unexpected indented block after @export".to_string(),
+            ))
+        } else {
+            Ok(Rc::new(Self {
+                names: args
+                    .into_iter()
+                    .map(|name| String::from(name.trim_start_matches('$')))
+                    .collect(),
+            }))
+        }
+    }
+}
@@ -1,47 +1,57 @@
+use crate::block::bytes::expression::Expression;
 use crate::block::{Block, MacroBlock};
-use crate::error::EvaluationError;
+use crate::error::{AnonymousEvaluationErrorResult, EvaluationError};
 use crate::evaluator::scope::EvaluatorScope;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
 use std::rc::Rc;
 
 pub struct RepeatBlock {
     line_number: usize,
-    repeat_count: usize,
+    repeat_count: Expression,
     blocks: Vec<Rc<dyn Block>>,
 }
 
 impl Block for RepeatBlock {
     fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        let repeat_count: BigInt = self
+            .repeat_count
+            .evaluate(scope)
+            .map_err_at(self.line_number)?;
+        let repeat_count: usize = repeat_count.to_usize().ok_or_else(|| {
+            EvaluationError::new(
+                self.line_number,
+                format!("invalid repetition count {}", repeat_count),
+            )
+        })?;
         let mut result: Vec<u8> = Vec::new();
+        let start: usize = scope.layout().position();
         for block in self.blocks.iter() {
+            scope.layout().seek(start + result.len());
             result.extend(block.evaluate(scope)?)
         }
-        Ok(result.repeat(self.repeat_count))
+        let result: Vec<u8> = result.repeat(repeat_count);
+        scope.layout().seek(start);
+        Ok(result)
     }
 }
 
 impl MacroBlock for RepeatBlock {
     fn allocate(
         line_number: usize,
-        mut args: Vec<String>,
+        args: Vec<String>,
         blocks: Vec<Rc<dyn Block>>,
     ) -> Result<Rc<Self>, EvaluationError> {
-        if args.len() == 1 {
-            let arg: String = args.pop().unwrap();
-            match arg.parse::<usize>() {
-                Ok(repeat_count) => Ok(Rc::new(Self {
-                    line_number,
-                    repeat_count,
-                    blocks,
-                })),
-                Err(_) => Err(EvaluationError::new(
-                    line_number,
-                    format!("invalid repetition count {}", arg),
-                )),
-            }
+        if !args.is_empty() {
+            Ok(Rc::new(Self {
+                line_number,
+                repeat_count: Expression::new(&args.join(" ")).map_err_at(line_number)?,
+                blocks,
+            }))
         } else {
             Err(EvaluationError::new(
                 line_number,
-                "expected exactly one argument indicating repetition count".to_string(),
+                "expected an expression indicating repetition count".to_string(),
             ))
         }
     }
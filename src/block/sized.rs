@@ -0,0 +1,52 @@
+use crate::block::bytes::translate::IntegerType;
+use crate::block::{Block, MacroBlock};
+use crate::error::{AnonymousEvaluationErrorResult, EvaluationError};
+use crate::evaluator::scope::EvaluatorScope;
+use num_bigint::BigInt;
+use std::rc::Rc;
+
+pub struct SizedBlock {
+    line_number: usize,
+    integer_type: IntegerType,
+    blocks: Vec<Rc<dyn Block>>,
+}
+
+impl Block for SizedBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        let start: usize = scope.layout().position();
+        let size: usize = self.integer_type.size();
+        // The length is reserved as a placeholder and patched in once the body is laid out after it
+        let mut result: Vec<u8> = vec![0; size];
+        for block in self.blocks.iter() {
+            scope.layout().seek(start + result.len());
+            result.extend(block.evaluate(scope)?);
+        }
+        scope.layout().seek(start);
+        let length: Vec<u8> = self
+            .integer_type
+            .encode(&BigInt::from(result.len() - size))
+            .map_err_at(self.line_number)?;
+        result[..size].copy_from_slice(&length);
+        Ok(result)
+    }
+}
+
+impl MacroBlock for SizedBlock {
+    fn allocate(
+        line_number: usize,
+        args: Vec<String>,
+        blocks: Vec<Rc<dyn Block>>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        match args.as_slice() {
+            [name] => Ok(Rc::new(Self {
+                line_number,
+                integer_type: IntegerType::from_name(name).map_err_at(line_number)?,
+                blocks,
+            })),
+            _ => Err(EvaluationError::new(
+                line_number,
+                "expected exactly one argument indicating length type".to_string(),
+            )),
+        }
+    }
+}
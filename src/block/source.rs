@@ -0,0 +1,30 @@
+use crate::block::Block;
+use crate::error::EvaluationError;
+use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
+use std::rc::Rc;
+
+pub struct SourceBlock {
+    file: Rc<String>,
+    block: Rc<dyn Block>,
+}
+
+impl SourceBlock {
+    pub fn new(file: Rc<String>, block: Rc<dyn Block>) -> Rc<Self> {
+        Rc::new(Self { file, block })
+    }
+}
+
+impl Block for SourceBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        self.block
+            .evaluate(scope)
+            .map_err(|e| e.in_file(&self.file))
+    }
+
+    fn evaluate_value(&self, scope: &mut EvaluatorScope) -> Result<Value, EvaluationError> {
+        self.block
+            .evaluate_value(scope)
+            .map_err(|e| e.in_file(&self.file))
+    }
+}
@@ -0,0 +1,112 @@
+pub mod expansion;
+mod field;
+
+use crate::block::structure::expansion::StructExpansion;
+use crate::block::structure::field::Field;
+use crate::block::{Block, RawMacroBlock};
+use crate::error::{AnonymousEvaluationErrorResult, EvaluationError};
+use crate::evaluator::expansion::StructLayout;
+use crate::evaluator::scope::EvaluatorScope;
+use std::rc::Rc;
+
+pub struct Structure {
+    pub name: String,
+    pub fields: Vec<Field>,
+    pub size: usize,
+}
+
+impl StructLayout for Structure {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn offset(&self, field: &str) -> Option<usize> {
+        self.fields
+            .iter()
+            .find(|other| other.name == field)
+            .map(|other| other.offset)
+    }
+}
+
+pub struct StructBlock {
+    structure: Rc<Structure>,
+}
+
+impl Block for StructBlock {
+    fn evaluate(&self, scope: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        scope.set(
+            &self.structure.name,
+            Box::new(StructExpansion::new(self.structure.clone())),
+        );
+        Ok(Vec::new())
+    }
+}
+
+fn align(offset: usize, alignment: usize) -> usize {
+    offset.div_ceil(alignment) * alignment
+}
+
+impl RawMacroBlock for StructBlock {
+    fn allocate(
+        line_number: usize,
+        mut args: Vec<String>,
+        lines: Vec<String>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        let packed: bool = match args.get(1).map(|arg| arg.as_str()) {
+            _ if args.is_empty() || args.len() > 2 => {
+                return Err(EvaluationError::new(
+                    line_number,
+                    "expected a struct name, optionally followed by packed".to_string(),
+                ))
+            }
+            None => false,
+            Some("packed") => true,
+            Some(other) => {
+                return Err(EvaluationError::new(
+                    line_number,
+                    format!("invalid struct option {}", other),
+                ))
+            }
+        };
+        let name: String = args.remove(0);
+
+        let mut fields: Vec<Field> = Vec::new();
+        let mut offset: usize = 0;
+        let mut alignment: usize = 1;
+        for (i, line) in lines.iter().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut field: Field = Field::new(line).map_err_at(line_number + 1 + i)?;
+            if fields.iter().any(|other| other.name == field.name) {
+                return Err(EvaluationError::new(
+                    line_number + 1 + i,
+                    format!(
+                        "struct {} has more than one field named {}",
+                        name, field.name
+                    ),
+                ));
+            }
+            // C layout places each field at a multiple of its alignment
+            if !packed {
+                offset = align(offset, field.alignment());
+                alignment = alignment.max(field.alignment());
+            }
+            field.offset = offset;
+            offset += field.size();
+            fields.push(field);
+        }
+        Ok(Rc::new(Self {
+            structure: Rc::new(Structure {
+                name,
+                fields,
+                size: align(offset, alignment),
+            }),
+        }))
+    }
+}
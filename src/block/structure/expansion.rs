@@ -0,0 +1,106 @@
+use crate::block::structure::field::Field;
+use crate::block::structure::Structure;
+use crate::error::AnonymousEvaluationError;
+use crate::evaluator::expansion::{Arguments, Expansion, StructLayout, Thunk};
+use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
+use std::rc::Rc;
+
+pub struct StructExpansion {
+    structure: Rc<Structure>,
+}
+
+impl StructExpansion {
+    pub fn new(structure: Rc<Structure>) -> Self {
+        Self { structure }
+    }
+
+    // Assigns arguments to fields, positionally in declaration order or by name
+    fn bind<'a>(
+        &self,
+        args: &Arguments<'a>,
+    ) -> Result<Vec<Option<&'a dyn Thunk>>, AnonymousEvaluationError> {
+        let fields: &[Field] = &self.structure.fields;
+        if args.positional.len() > fields.len() {
+            return Err(AnonymousEvaluationError::new(format!(
+                "struct ${} expected at most {} args, got {}",
+                self.structure.name,
+                fields.len(),
+                args.positional.len()
+            )));
+        }
+        let mut result: Vec<Option<&dyn Thunk>> = vec![None; fields.len()];
+        for (index, thunk) in args.positional.iter().enumerate() {
+            result[index] = Some(*thunk);
+        }
+        for (name, thunk) in args.named.iter() {
+            let index: usize = fields
+                .iter()
+                .position(|field| field.name == *name)
+                .ok_or_else(|| {
+                    AnonymousEvaluationError::new(format!(
+                        "struct ${} has no field named {}",
+                        self.structure.name, name
+                    ))
+                })?;
+            if result[index].is_some() {
+                return Err(AnonymousEvaluationError::new(format!(
+                    "struct ${} got multiple values for field {}",
+                    self.structure.name, name
+                )));
+            }
+            result[index] = Some(*thunk);
+        }
+        Ok(result)
+    }
+}
+
+fn encode_field(field: &Field, value: &Value) -> Result<Vec<u8>, AnonymousEvaluationError> {
+    let bytes: Vec<u8> = match (field.count, value) {
+        (None, value) => field.integer_type.encode(&value.to_integer()?)?,
+        (Some(_), Value::List(values)) => {
+            let mut result: Vec<u8> = Vec::new();
+            for value in values {
+                result.extend(field.integer_type.encode(&value.to_integer()?)?);
+            }
+            result
+        }
+        (Some(_), value) => value.to_bytes()?,
+    };
+    if bytes.len() > field.size() {
+        return Err(AnonymousEvaluationError::new(format!(
+            "value for field {} is {} bytes, but the field is {} bytes",
+            field.name,
+            bytes.len(),
+            field.size()
+        )));
+    }
+    Ok(bytes)
+}
+
+impl Expansion for StructExpansion {
+    fn expand(
+        &self,
+        _: &EvaluatorScope,
+        args: &Arguments,
+    ) -> Result<Value, AnonymousEvaluationError> {
+        let values: Vec<Option<&dyn Thunk>> = self.bind(args)?;
+        let mut result: Vec<u8> = vec![0; self.structure.size];
+        let layout = args.scope.layout();
+        let start: usize = layout.position();
+        for (field, thunk) in self.structure.fields.iter().zip(values) {
+            if let Some(thunk) = thunk {
+                // Each value sees $here at its own field
+                layout.seek(start + field.offset);
+                let bytes: Vec<u8> = encode_field(field, &thunk.force(args.scope)?)?;
+                result[field.offset..field.offset + bytes.len()].copy_from_slice(&bytes);
+            }
+        }
+        layout.seek(start);
+        Ok(Value::Bytes(result))
+    }
+
+    fn structure(&self) -> Option<&dyn StructLayout> {
+        Some(self.structure.as_ref())
+    }
+}
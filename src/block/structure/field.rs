@@ -0,0 +1,62 @@
+use crate::block::bytes::translate::{big_integer_from_number, IntegerType};
+use crate::error::AnonymousEvaluationError;
+use num_traits::ToPrimitive;
+
+pub struct Field {
+    pub name: String,
+    pub integer_type: IntegerType,
+    pub count: Option<usize>,
+    pub offset: usize,
+}
+
+// Pointers are little-endian, as on the targets payloads are usually written for
+fn resolve_type(name: &str) -> Result<IntegerType, AnonymousEvaluationError> {
+    match name {
+        "ptr32" => IntegerType::from_name("u32le"),
+        "ptr64" => IntegerType::from_name("u64le"),
+        _ => IntegerType::from_name(name),
+    }
+}
+
+impl Field {
+    pub fn new(line: &str) -> Result<Self, AnonymousEvaluationError> {
+        let error = || {
+            AnonymousEvaluationError::new(format!(
+                "expected a field of the form type name or type[count] name, got {}",
+                line
+            ))
+        };
+        let words: Vec<&str> = line.split_ascii_whitespace().collect();
+        let (field_type, name): (&str, &str) = match words.as_slice() {
+            [field_type, name] => (field_type, name),
+            _ => return Err(error()),
+        };
+        let (field_type, count): (&str, Option<usize>) = match field_type.find('[') {
+            Some(index) => {
+                // Counts follow the literal rules, so u8[10] holds sixteen elements
+                let count: usize = field_type[index + 1..]
+                    .strip_suffix(']')
+                    .and_then(|count| big_integer_from_number(count).ok())
+                    .and_then(|count| count.to_usize())
+                    .ok_or_else(error)?;
+                (&field_type[..index], Some(count))
+            }
+            None => (field_type, None),
+        };
+        Ok(Self {
+            name: String::from(name),
+            integer_type: resolve_type(field_type)?,
+            count,
+            offset: 0,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.integer_type.size() * self.count.unwrap_or(1)
+    }
+
+    // Natural alignment is that of a single element
+    pub fn alignment(&self) -> usize {
+        self.integer_type.size()
+    }
+}
@@ -0,0 +1,47 @@
+use super::{Block, RawMacroBlock};
+use crate::error::EvaluationError;
+use crate::evaluator::scope::EvaluatorScope;
+use std::rc::Rc;
+
+pub struct TextBlock {
+    text: Vec<u8>,
+}
+
+impl Block for TextBlock {
+    fn evaluate(&self, _: &mut EvaluatorScope) -> Result<Vec<u8>, EvaluationError> {
+        Ok(self.text.clone())
+    }
+}
+
+impl RawMacroBlock for TextBlock {
+    fn allocate(
+        line_number: usize,
+        args: Vec<String>,
+        lines: Vec<String>,
+    ) -> Result<Rc<Self>, EvaluationError> {
+        let mut line_ending: &str = "\n";
+        let mut trailing: bool = true;
+        for arg in args.iter() {
+            match arg.as_str() {
+                "lf" => line_ending = "\n",
+                "crlf" => line_ending = "\r\n",
+                "newline" => trailing = true,
+                "nonewline" => trailing = false,
+                _ => {
+                    return Err(EvaluationError::new(
+                        line_number,
+                        format!("invalid text option {}", arg),
+                    ))
+                }
+            }
+        }
+
+        let mut text: String = lines.join(line_ending);
+        if trailing && !lines.is_empty() {
+            text.push_str(line_ending);
+        }
+        Ok(Rc::new(Self {
+            text: text.into_bytes(),
+        }))
+    }
+}
@@ -22,14 +22,51 @@ impl fmt::Display for ParserError {
 
 pub struct AnonymousEvaluationError {
     what: String,
+    recursion: bool,
+    located: Option<EvaluationError>,
+    lines: usize,
 }
 
 impl AnonymousEvaluationError {
     pub fn new(what: String) -> Self {
-        Self { what }
+        Self {
+            what,
+            recursion: false,
+            located: None,
+            lines: 0,
+        }
+    }
+    pub fn recursion(what: String) -> Self {
+        Self {
+            what,
+            recursion: true,
+            located: None,
+            lines: 0,
+        }
+    }
+    pub fn propagate(error: EvaluationError) -> Self {
+        Self {
+            what: error.what.clone(),
+            recursion: error.recursion,
+            located: Some(error),
+            lines: 0,
+        }
+    }
+    // Errors in text spanning several lines are reported relative to its first line
+    pub fn below(mut self, lines: usize) -> Self {
+        self.lines = lines;
+        self
     }
     pub fn at(self, line: usize) -> EvaluationError {
-        EvaluationError::new(line, self.what)
+        match self.located {
+            Some(error) => error,
+            None => EvaluationError {
+                what: self.what,
+                line: line + self.lines,
+                file: None,
+                recursion: self.recursion,
+            },
+        }
     }
 }
 
@@ -47,18 +84,43 @@ impl<T> AnonymousEvaluationErrorResult<T> for Result<T, AnonymousEvaluationError
 pub struct EvaluationError {
     what: String,
     line: usize,
+    file: Option<String>,
+    recursion: bool,
 }
 
 impl Error for EvaluationError {}
 
 impl fmt::Display for EvaluationError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Runtime error on line {}: {}", self.line, self.what)
+        match &self.file {
+            Some(file) => write!(
+                f,
+                "Runtime error in {} on line {}: {}",
+                file, self.line, self.what
+            ),
+            None => write!(f, "Runtime error on line {}: {}", self.line, self.what),
+        }
     }
 }
 
 impl EvaluationError {
     pub fn new(line: usize, what: String) -> Self {
-        EvaluationError { what, line }
+        EvaluationError {
+            what,
+            line,
+            file: None,
+            recursion: false,
+        }
+    }
+
+    pub fn is_recursion(&self) -> bool {
+        self.recursion
+    }
+
+    pub fn in_file(mut self, file: &str) -> Self {
+        if self.file.is_none() {
+            self.file = Some(String::from(file));
+        }
+        self
     }
 }
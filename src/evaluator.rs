@@ -1,19 +1,74 @@
 pub mod expansion;
+pub mod layout;
+pub mod module;
 pub mod scope;
+pub mod value;
 
 use crate::block::Block;
 use crate::error::EvaluationError;
+use crate::evaluator::layout::{Layout, Resolution};
 use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
 use std::rc::Rc;
 
+// Forward label references settle in two passes unless a label moves the layout
+const MAXIMUM_PASSES: usize = 8;
+
+pub fn evaluate_payload(
+    blocks: &Vec<Rc<dyn Block>>,
+    recursion_limit: usize,
+) -> Result<Vec<u8>, EvaluationError> {
+    let mut previous: Option<Resolution> = None;
+    let mut pass: usize = 1;
+    loop {
+        let layout: Rc<Layout> = Layout::new(previous.clone(), pass == MAXIMUM_PASSES);
+        let mut scope = EvaluatorScope::new();
+        scope.set_recursion_limit(recursion_limit);
+        scope.set_layout(layout.clone());
+        let result = evaluate(blocks, &scope);
+        let resolution: Resolution = layout.resolution(result.is_ok());
+        // A failed pass is only retried while it still learns new label values
+        let learned: bool = match &previous {
+            Some(previous) => {
+                previous.labels != resolution.labels || previous.base != resolution.base
+            }
+            None => true,
+        };
+        if layout.is_settled() || (result.is_err() && !learned) {
+            layout.flush();
+            return result;
+        }
+        if pass == MAXIMUM_PASSES {
+            return Err(result.err().unwrap_or_else(|| layout.divergence(pass)));
+        }
+        previous = Some(resolution);
+        pass += 1;
+    }
+}
+
 pub fn evaluate(
     blocks: &Vec<Rc<dyn Block>>,
     scope: &EvaluatorScope,
 ) -> Result<Vec<u8>, EvaluationError> {
     let mut result: Vec<u8> = Vec::new();
     let mut inner = EvaluatorScope::child(scope);
+    let start: usize = scope.layout().position();
     for block in blocks.iter() {
+        inner.layout().seek(start + result.len());
         result.extend(block.evaluate(&mut inner)?);
     }
+    scope.layout().seek(start);
     Ok(result)
 }
+
+pub fn evaluate_value(
+    blocks: &Vec<Rc<dyn Block>>,
+    scope: &EvaluatorScope,
+) -> Result<Value, EvaluationError> {
+    if blocks.len() == 1 {
+        let mut inner = EvaluatorScope::child(scope);
+        blocks[0].evaluate_value(&mut inner)
+    } else {
+        Ok(Value::Bytes(evaluate(blocks, scope)?))
+    }
+}
@@ -1,21 +1,57 @@
 use crate::error::AnonymousEvaluationError;
 use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::value::Value;
+
+pub trait Thunk {
+    fn force(&self, scope: &EvaluatorScope) -> Result<Value, AnonymousEvaluationError>;
+
+    fn delay(&self, scope: &EvaluatorScope) -> Box<dyn Expansion>;
+}
+
+pub struct Arguments<'a> {
+    pub scope: &'a EvaluatorScope<'a>,
+    pub positional: Vec<&'a dyn Thunk>,
+    pub named: Vec<(String, &'a dyn Thunk)>,
+}
+
+impl<'a> Arguments<'a> {
+    pub fn len(&self) -> usize {
+        self.positional.len() + self.named.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// The layout of a struct, as seen by $sizeof and $offsetof
+pub trait StructLayout {
+    fn name(&self) -> &str;
+
+    fn size(&self) -> usize;
+
+    fn offset(&self, field: &str) -> Option<usize>;
+}
 
 pub trait Expansion {
     fn expand(
         &self,
         scope: &EvaluatorScope,
-        args: &Vec<Vec<u8>>,
-    ) -> Result<Vec<u8>, AnonymousEvaluationError>;
+        args: &Arguments,
+    ) -> Result<Value, AnonymousEvaluationError>;
+
+    fn structure(&self) -> Option<&dyn StructLayout> {
+        None
+    }
 }
 
 pub struct InlineExpansion {
     name: String,
-    value: Vec<u8>,
+    value: Value,
 }
 
 impl InlineExpansion {
-    pub fn new(name: String, value: Vec<u8>) -> Box<Self> {
+    pub fn new(name: String, value: Value) -> Box<Self> {
         Box::new(Self { name, value })
     }
 }
@@ -24,8 +60,8 @@ impl Expansion for InlineExpansion {
     fn expand(
         &self,
         _: &EvaluatorScope,
-        args: &Vec<Vec<u8>>,
-    ) -> Result<Vec<u8>, AnonymousEvaluationError> {
+        args: &Arguments,
+    ) -> Result<Value, AnonymousEvaluationError> {
         if !args.is_empty() {
             Err(AnonymousEvaluationError::new(format!(
                 "expansion ${} expected 0 args, got {}",
@@ -0,0 +1,209 @@
+use crate::error::{AnonymousEvaluationError, EvaluationError};
+use num_bigint::BigInt;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+// Labels are keyed by the label scope they were defined in, then by name
+type Key = (usize, String);
+
+// The offset of each label and the line it was defined on
+pub type Labels = HashMap<Key, (usize, usize)>;
+
+// Label values and the base address settled by the previous evaluation pass
+#[derive(Clone, Default, PartialEq)]
+pub struct Resolution {
+    pub labels: Labels,
+    pub base: Option<BigInt>,
+    pub complete: bool,
+}
+
+pub struct Layout {
+    position: Cell<usize>,
+    scopes: Cell<usize>,
+    labels: RefCell<Labels>,
+    base: RefCell<Option<BigInt>>,
+    base_line: Cell<usize>,
+    previous: Option<Resolution>,
+    reads: RefCell<HashSet<Key>>,
+    stale_base: Cell<bool>,
+    strict: bool,
+    messages: RefCell<Vec<String>>,
+}
+
+impl Layout {
+    pub fn new(previous: Option<Resolution>, strict: bool) -> Rc<Self> {
+        Rc::new(Self {
+            position: Cell::new(0),
+            scopes: Cell::new(1),
+            labels: RefCell::new(HashMap::new()),
+            base: RefCell::new(None),
+            base_line: Cell::new(0),
+            previous,
+            reads: RefCell::new(HashSet::new()),
+            stale_base: Cell::new(false),
+            strict,
+            messages: RefCell::new(Vec::new()),
+        })
+    }
+
+    // The offset in the payload at which the next byte will be emitted
+    pub fn position(&self) -> usize {
+        self.position.get()
+    }
+
+    pub fn seek(&self, position: usize) {
+        self.position.set(position);
+    }
+
+    // Scope 0 is the payload itself, and each expansion or iteration opens the next one
+    pub fn open(&self) -> usize {
+        let scope: usize = self.scopes.get();
+        self.scopes.set(scope + 1);
+        scope
+    }
+
+    pub fn define(
+        &self,
+        path: &[usize],
+        name: &str,
+        line_number: usize,
+    ) -> Result<(), AnonymousEvaluationError> {
+        let key: Key = (*path.last().unwrap(), String::from(name));
+        let mut labels = self.labels.borrow_mut();
+        if let Some((_, line)) = labels.get(&key) {
+            return Err(AnonymousEvaluationError::new(format!(
+                "label {} is already defined on line {}",
+                name, line
+            )));
+        }
+        // On the last pass, a label read ahead of itself must land where the previous pass put it
+        if self.strict && self.reads.borrow().contains(&key) {
+            let previous: Option<usize> = self
+                .previous
+                .as_ref()
+                .and_then(|previous| previous.labels.get(&key))
+                .map(|(offset, _)| *offset);
+            if previous != Some(self.position()) {
+                return Err(AnonymousEvaluationError::new(format!(
+                    "label {} did not converge to a fixed offset",
+                    name
+                )));
+            }
+        }
+        labels.insert(key, (self.position(), line_number));
+        Ok(())
+    }
+
+    pub fn set_base(&self, base: BigInt, line_number: usize) {
+        *self.base.borrow_mut() = Some(base);
+        self.base_line.set(line_number);
+    }
+
+    pub fn base(&self) -> BigInt {
+        if let Some(base) = self.base.borrow().as_ref() {
+            return base.clone();
+        }
+        self.stale_base.set(true);
+        self.previous
+            .as_ref()
+            .and_then(|previous| previous.base.clone())
+            .unwrap_or_default()
+    }
+
+    // Labels defined earlier in this pass are exact, later ones come from the previous pass
+    pub fn offset(&self, path: &[usize], name: &str) -> Result<usize, AnonymousEvaluationError> {
+        let keys: Vec<Key> = path
+            .iter()
+            .rev()
+            .map(|scope| (*scope, String::from(name)))
+            .collect();
+        let labels = self.labels.borrow();
+        if let Some((offset, _)) = keys.iter().find_map(|key| labels.get(key)) {
+            return Ok(*offset);
+        }
+        let previous: Option<(&Key, usize)> = self.previous.as_ref().and_then(|previous| {
+            keys.iter()
+                .find_map(|key| previous.labels.get(key).map(|(offset, _)| (key, *offset)))
+        });
+        let mut reads = self.reads.borrow_mut();
+        match (previous, &self.previous) {
+            (Some((key, offset)), _) => {
+                reads.insert(key.clone());
+                Ok(offset)
+            }
+            (None, Some(previous)) if previous.complete => Err(AnonymousEvaluationError::new(
+                format!("undefined label {}", name),
+            )),
+            (None, _) => {
+                reads.insert(keys[0].clone());
+                Ok(0)
+            }
+        }
+    }
+
+    pub fn report(&self, message: String) {
+        self.messages.borrow_mut().push(message);
+    }
+
+    pub fn flush(&self) {
+        for message in self.messages.borrow().iter() {
+            eprintln!("{}", message);
+        }
+    }
+
+    // Whether every value read ahead of its definition matches the value it resolved to
+    pub fn is_settled(&self) -> bool {
+        let previous: Resolution = self.previous.clone().unwrap_or_default();
+        let labels = self.labels.borrow();
+        (!self.stale_base.get() || *self.base.borrow() == previous.base)
+            && self.reads.borrow().iter().all(|key| {
+                let offset: Option<usize> = labels.get(key).map(|(offset, _)| *offset);
+                offset.is_some() && offset == previous.labels.get(key).map(|(offset, _)| *offset)
+            })
+    }
+
+    // Points at a label, or failing that the base address, that kept moving between passes
+    pub fn divergence(&self, passes: usize) -> EvaluationError {
+        let previous: Resolution = self.previous.clone().unwrap_or_default();
+        let labels = self.labels.borrow();
+        let reads = self.reads.borrow();
+        let moved: Option<(usize, &str)> = reads
+            .iter()
+            .filter_map(|key| {
+                let current: Option<&(usize, usize)> = labels.get(key);
+                let earlier: Option<&(usize, usize)> = previous.labels.get(key);
+                match current.or(earlier) {
+                    Some((_, line)) if current != earlier => Some((*line, key.1.as_str())),
+                    _ => None,
+                }
+            })
+            .min();
+        match moved {
+            Some((line, name)) => EvaluationError::new(
+                line,
+                format!(
+                    "label {} did not converge to a fixed offset after {} passes",
+                    name, passes
+                ),
+            ),
+            None => EvaluationError::new(
+                self.base_line.get(),
+                format!("base address did not converge after {} passes", passes),
+            ),
+        }
+    }
+
+    // Labels this pass did not reach keep their previous value
+    pub fn resolution(&self, complete: bool) -> Resolution {
+        let mut result: Resolution = self.previous.clone().unwrap_or_default();
+        result.complete = complete;
+        for (key, label) in self.labels.borrow().iter() {
+            result.labels.insert(key.clone(), *label);
+        }
+        if let Some(base) = self.base.borrow().as_ref() {
+            result.base = Some(base.clone());
+        }
+        result
+    }
+}
@@ -0,0 +1,39 @@
+use crate::error::AnonymousEvaluationError;
+use crate::evaluator::expansion::{Arguments, Expansion, StructLayout};
+use crate::evaluator::scope::{EvaluatorScope, Expansions};
+use crate::evaluator::value::Value;
+use std::rc::Rc;
+
+pub type Module = Rc<Expansions>;
+
+pub struct ModuleExpansion {
+    module: Module,
+    name: String,
+}
+
+impl ModuleExpansion {
+    pub fn new(module: Module, name: String) -> Box<Self> {
+        Box::new(Self { module, name })
+    }
+}
+
+impl Expansion for ModuleExpansion {
+    fn expand(
+        &self,
+        scope: &EvaluatorScope,
+        args: &Arguments,
+    ) -> Result<Value, AnonymousEvaluationError> {
+        let mut inner: EvaluatorScope = EvaluatorScope::child(scope);
+        for name in self.module.keys() {
+            inner.set(
+                name,
+                ModuleExpansion::new(self.module.clone(), name.clone()),
+            );
+        }
+        self.module[&self.name].expand(&inner, args)
+    }
+
+    fn structure(&self) -> Option<&dyn StructLayout> {
+        self.module[&self.name].structure()
+    }
+}
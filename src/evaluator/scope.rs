@@ -1,44 +1,282 @@
+use crate::error::AnonymousEvaluationError;
 use crate::evaluator::expansion::Expansion;
-use std::collections::HashMap;
+use crate::evaluator::layout::Layout;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 type Link<'a> = Option<&'a EvaluatorScope<'a>>;
+pub type Expansions = HashMap<String, Rc<dyn Expansion>>;
+
+pub const DEFAULT_RECURSION_LIMIT: usize = 256;
+
+pub struct Boundary {
+    name: String,
+    warned: RefCell<HashSet<String>>,
+}
+
+impl Boundary {
+    pub fn new(name: String) -> Rc<Self> {
+        Rc::new(Self {
+            name,
+            warned: RefCell::new(HashSet::new()),
+        })
+    }
+
+    fn warn(&self, name: &str, layout: &Layout) {
+        if self.warned.borrow_mut().insert(String::from(name)) {
+            layout.report(format!(
+                "warning: definition ${} resolves ${} dynamically from the calling scope",
+                self.name, name
+            ));
+        }
+    }
+}
+
+// Each definition of a name along with the version of the scope that made it
+type History = Vec<(usize, Rc<dyn Expansion>)>;
+
+// Every definition made in a scope, in order, so that a capture sees the scope as it was
+#[derive(Default)]
+struct Frame {
+    expansions: RefCell<HashMap<String, History>>,
+    version: Cell<usize>,
+}
+
+impl Frame {
+    fn set(&self, name: &str, expansion: Rc<dyn Expansion>) {
+        let version: usize = self.version.get();
+        self.expansions
+            .borrow_mut()
+            .entry(String::from(name))
+            .or_default()
+            .push((version, expansion));
+        self.version.set(version + 1);
+    }
+
+    // The latest definition of a name made before the given version
+    fn get(&self, name: &str, version: usize) -> Option<Rc<dyn Expansion>> {
+        self.expansions
+            .borrow()
+            .get(name)?
+            .iter()
+            .rev()
+            .find(|(made, _)| *made < version)
+            .map(|(_, expansion)| expansion.clone())
+    }
+
+    fn latest(&self) -> Expansions {
+        self.expansions
+            .borrow()
+            .iter()
+            .filter_map(|(name, history)| {
+                let (_, expansion) = history.last()?;
+                Some((name.clone(), expansion.clone()))
+            })
+            .collect()
+    }
+}
+
+// The definitions visible where a lexical definition appears, one scope per link
+pub struct Environment {
+    frame: Rc<Frame>,
+    version: usize,
+    parent: Option<Rc<Environment>>,
+}
+
+impl Environment {
+    fn get(&self, name: &str) -> Option<Rc<dyn Expansion>> {
+        let mut cursor: Option<&Environment> = Some(self);
+        while let Some(environment) = cursor {
+            if let Some(expansion) = environment.frame.get(name, environment.version) {
+                return Some(expansion);
+            }
+            cursor = environment.parent.as_deref();
+        }
+        None
+    }
+}
 
 pub struct EvaluatorScope<'a> {
-    expansions: HashMap<String, Box<dyn Expansion>>,
+    frame: Rc<Frame>,
+    environment: Option<Rc<Environment>>,
     parent: Link<'a>,
+    captured: RefCell<Option<Rc<Environment>>>,
+    exports: Vec<String>,
+    boundary: Option<Rc<Boundary>>,
+    layout: Rc<Layout>,
+    labels: Rc<Vec<usize>>,
+    depth: usize,
+    limit: usize,
 }
 
 impl<'a> EvaluatorScope<'a> {
     pub fn new() -> Self {
         Self {
-            expansions: HashMap::new(),
+            frame: Rc::default(),
+            environment: None,
             parent: None,
+            captured: RefCell::new(None),
+            exports: Vec::new(),
+            boundary: None,
+            layout: Layout::new(None, false),
+            labels: Rc::new(vec![0]),
+            depth: 0,
+            limit: DEFAULT_RECURSION_LIMIT,
         }
     }
 
     pub fn child(parent: &'a EvaluatorScope<'a>) -> Self {
         Self {
-            expansions: HashMap::new(),
+            frame: Rc::default(),
+            environment: None,
             parent: Some(parent),
+            captured: RefCell::new(None),
+            exports: Vec::new(),
+            boundary: None,
+            layout: parent.layout.clone(),
+            labels: parent.labels.clone(),
+            depth: parent.depth,
+            limit: parent.limit,
         }
     }
 
+    pub fn lexical(
+        parent: &'a EvaluatorScope<'a>,
+        expansions: Expansions,
+        environment: Rc<Environment>,
+        boundary: Rc<Boundary>,
+    ) -> Self {
+        let frame: Rc<Frame> = Rc::default();
+        for (name, expansion) in expansions {
+            frame.set(&name, expansion);
+        }
+        Self {
+            frame,
+            environment: Some(environment),
+            parent: Some(parent),
+            captured: RefCell::new(None),
+            exports: Vec::new(),
+            boundary: Some(boundary),
+            layout: parent.layout.clone(),
+            labels: parent.labels.clone(),
+            depth: parent.depth,
+            limit: parent.limit,
+        }
+    }
+
+    pub fn detached(&self, environment: Option<Rc<Environment>>) -> EvaluatorScope<'static> {
+        EvaluatorScope {
+            frame: Rc::default(),
+            environment,
+            parent: None,
+            captured: RefCell::new(None),
+            exports: Vec::new(),
+            boundary: None,
+            layout: self.layout.clone(),
+            labels: self.labels.clone(),
+            depth: self.depth,
+            limit: self.limit,
+        }
+    }
+
+    pub fn set_layout(&mut self, layout: Rc<Layout>) {
+        self.layout = layout;
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    // Labels defined from here on are local to this scope and the scopes within it
+    pub fn open_labels(&mut self) {
+        let mut labels: Vec<usize> = (*self.labels).clone();
+        labels.push(self.layout.open());
+        self.labels = Rc::new(labels);
+    }
+
+    pub fn define_label(
+        &self,
+        name: &str,
+        line_number: usize,
+    ) -> Result<(), AnonymousEvaluationError> {
+        self.layout.define(&self.labels, name, line_number)
+    }
+
+    pub fn label_offset(&self, name: &str) -> Result<usize, AnonymousEvaluationError> {
+        self.layout.offset(&self.labels, name)
+    }
+
+    pub fn set_recursion_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    pub fn enter(&mut self, name: &str) -> Result<(), AnonymousEvaluationError> {
+        if self.depth >= self.limit {
+            return Err(AnonymousEvaluationError::recursion(format!(
+                "recursion depth exceeded at ${} (limit {})",
+                name, self.limit
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
     pub fn set(&mut self, name: &String, expansion: Box<dyn Expansion>) {
-        self.expansions.insert(name.clone(), expansion);
+        self.frame.set(name, Rc::from(expansion));
     }
 
-    pub fn get(&self, name: &String) -> Option<&Box<dyn Expansion>> {
-        if let Some(expansion) = self.expansions.get(name) {
-            Some(expansion)
-        } else {
-            let mut cursor: &Link = &self.parent;
-            while let Some(scope) = cursor {
-                if let Some(expansion) = scope.expansions.get(name) {
-                    return Some(expansion);
+    pub fn get(&self, name: &str) -> Option<Rc<dyn Expansion>> {
+        let mut boundary: Option<&Rc<Boundary>> = None;
+        let mut cursor: Link = Some(self);
+        while let Some(scope) = cursor {
+            let version: usize = scope.frame.version.get();
+            let expansion: Option<Rc<dyn Expansion>> =
+                scope.frame.get(name, version).or_else(|| {
+                    scope
+                        .environment
+                        .as_ref()
+                        .and_then(|environment| environment.get(name))
+                });
+            if let Some(expansion) = expansion {
+                if let Some(boundary) = boundary {
+                    boundary.warn(name, &self.layout);
                 }
-                cursor = &scope.parent;
+                return Some(expansion);
+            }
+            boundary = boundary.or(scope.boundary.as_ref());
+            cursor = scope.parent;
+        }
+        None
+    }
+
+    // Links to this scope as it is now, and to its enclosing scopes, which cannot change meanwhile
+    pub fn capture(&self) -> Rc<Environment> {
+        let version: usize = self.frame.version.get();
+        if let Some(captured) = self.captured.borrow().as_ref() {
+            if captured.version == version {
+                return captured.clone();
             }
-            None
         }
+        let parent: Option<Rc<Environment>> = match (&self.environment, self.parent) {
+            (Some(environment), _) => Some(environment.clone()),
+            (None, Some(parent)) => Some(parent.capture()),
+            (None, None) => None,
+        };
+        let captured: Rc<Environment> = Rc::new(Environment {
+            frame: self.frame.clone(),
+            version,
+            parent,
+        });
+        *self.captured.borrow_mut() = Some(captured.clone());
+        captured
+    }
+
+    pub fn export(&mut self, name: &str) {
+        self.exports.push(String::from(name));
+    }
+
+    pub fn into_parts(self) -> (Expansions, Vec<String>) {
+        (self.frame.latest(), self.exports)
     }
 }
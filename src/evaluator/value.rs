@@ -0,0 +1,68 @@
+use crate::error::AnonymousEvaluationError;
+use num_bigint::{BigInt, Sign};
+use num_traits::{Signed, Zero};
+
+#[derive(Clone)]
+pub enum Value {
+    Integer(BigInt),
+    Bytes(Vec<u8>),
+    // Bytes written as a number, which unlike other bytes may be read back as an integer
+    Numeral(Vec<u8>),
+    String(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AnonymousEvaluationError> {
+        match self {
+            Value::Integer(value) if value.is_negative() => {
+                Err(AnonymousEvaluationError::new(format!(
+                    "negative integer {} must be packed with an explicit size",
+                    value
+                )))
+            }
+            Value::Integer(value) if value.is_zero() => Ok(Vec::new()),
+            Value::Integer(value) => Ok(value.magnitude().to_bytes_be()),
+            Value::Bytes(bytes) | Value::Numeral(bytes) => Ok(bytes.clone()),
+            Value::String(string) => {
+                let mut result: Vec<u8> = Vec::new();
+                for character in string.chars() {
+                    if character as u32 > 0xFF {
+                        return Err(AnonymousEvaluationError::new(format!(
+                            "string contains character {:?} outside of latin1",
+                            character
+                        )));
+                    }
+                    result.push(character as u8);
+                }
+                Ok(result)
+            }
+            Value::List(values) => {
+                let mut result: Vec<u8> = Vec::new();
+                for value in values {
+                    result.extend(value.to_bytes()?);
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    pub fn to_integer(&self) -> Result<BigInt, AnonymousEvaluationError> {
+        match self {
+            Value::Integer(value) => Ok(value.clone()),
+            Value::Numeral(bytes) => Ok(BigInt::from_bytes_be(Sign::Plus, bytes)),
+            Value::Bytes(bytes) => Err(AnonymousEvaluationError::new(format!(
+                "cannot use {} bytes as an integer without $int(bytes, order)",
+                bytes.len()
+            ))),
+            Value::String(string) => Err(AnonymousEvaluationError::new(format!(
+                "cannot use string {:?} as an integer",
+                string
+            ))),
+            Value::List(values) => Err(AnonymousEvaluationError::new(format!(
+                "cannot use list of {} values as an integer",
+                values.len()
+            ))),
+        }
+    }
+}
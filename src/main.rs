@@ -8,25 +8,23 @@ extern crate pest;
 #[macro_use]
 extern crate pest_derive;
 
-use crate::evaluator::evaluate;
-use crate::evaluator::scope::EvaluatorScope;
+use crate::evaluator::evaluate_payload;
+use crate::evaluator::scope::DEFAULT_RECURSION_LIMIT;
 use clap::{App, Arg};
 use error::EvaluationError;
 use parser::parse;
-use std::fs::File;
-use std::io::{stdout, BufReader, Write};
+use std::io::{stdout, Write};
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::thread;
 
-fn read(path: &str) -> Result<Vec<u8>, EvaluationError> {
-    let file = match File::open(&path) {
-        Ok(file) => file,
-        Err(_) => return Err(EvaluationError::new(0, "error reading file!".to_string())),
-    };
-
-    let reader = BufReader::new(file);
-    let blocks = parse(reader)?;
-    let result = evaluate(&blocks, &EvaluatorScope::new())?;
-    Ok(result)
+fn read(
+    path: &str,
+    search_paths: &[PathBuf],
+    recursion_limit: usize,
+) -> Result<Vec<u8>, EvaluationError> {
+    let blocks = parse(Path::new(path), search_paths)?;
+    evaluate_payload(&blocks, recursion_limit)
 }
 
 fn encode_digit(digit: u8) -> char {
@@ -37,6 +35,12 @@ fn encode_digit(digit: u8) -> char {
     }
 }
 
+// Each nested expansion recurses through several blocks, so the stack grows with the limit
+const MINIMUM_STACK_SIZE: usize = 8 * 1024 * 1024;
+const STACK_SIZE_PER_EXPANSION: usize = 64 * 1024;
+// Keeps the stack reserved for the deepest allowed nesting to 512 MiB
+const MAXIMUM_RECURSION_LIMIT: usize = 8192;
+
 const DEBUG_COLUMN_WIDTH: usize = 8;
 const DEBUG_COLUMN_COUNT: usize = 2;
 
@@ -79,10 +83,60 @@ fn main() {
                 .long("debug")
                 .help("Prints the resultant bytes to STDIO has readable hex"),
         )
+        .arg(
+            Arg::with_name("include")
+                .short("I")
+                .value_name("DIRECTORY")
+                .help("Adds a directory to search for included and imported files")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("recursion-limit")
+                .long("recursion-limit")
+                .value_name("DEPTH")
+                .help("Sets the maximum depth of nested expansions, at most 8192")
+                .takes_value(true),
+        )
         .get_matches();
 
     let path = matches.value_of("file").unwrap();
-    let bytes = match read(path) {
+    let search_paths: Vec<PathBuf> = match matches.values_of("include") {
+        Some(values) => values.map(PathBuf::from).collect(),
+        None => Vec::new(),
+    };
+    let recursion_limit: usize = match matches.value_of("recursion-limit") {
+        Some(value) => match value.parse::<usize>() {
+            Ok(limit) if limit > MAXIMUM_RECURSION_LIMIT => {
+                eprintln!(
+                    "recursion limit {} exceeds the maximum of {}",
+                    limit, MAXIMUM_RECURSION_LIMIT
+                );
+                exit(1);
+            }
+            Ok(limit) => limit,
+            Err(_) => {
+                eprintln!("invalid recursion limit {}", value);
+                exit(1);
+            }
+        },
+        None => DEFAULT_RECURSION_LIMIT,
+    };
+    let path = String::from(path);
+    let reader = thread::Builder::new()
+        .stack_size(MINIMUM_STACK_SIZE.max(recursion_limit * STACK_SIZE_PER_EXPANSION))
+        .spawn(move || read(&path, &search_paths, recursion_limit));
+    let result = match reader {
+        Ok(handle) => handle
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+        Err(e) => {
+            eprintln!("error while starting evaluation: {}", e);
+            exit(1);
+        }
+    };
+    let bytes = match result {
         Ok(result) => result,
         Err(error) => {
             eprintln!("{}", error);
@@ -3,22 +3,39 @@ mod indentation;
 
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
 use crate::error::{AnonymousEvaluationError, AnonymousEvaluationErrorResult};
 use cursor::ParserCursor;
 use indentation::ParserIndentation;
 
 use crate::block::assembly::AssemblyBlock;
+use crate::block::binding::LetBlock;
 use crate::block::bytes::BytesBlock;
+use crate::block::conditional::IfBlock;
 use crate::block::define::DefineBlock;
+use crate::block::diagnostic::DiagnosticBlock;
+use crate::block::hex::HexBlock;
+use crate::block::iteration::ForBlock;
+use crate::block::layout::{BaseBlock, LabelBlock, PaddingBlock};
+use crate::block::module::{ExportBlock, ModuleBlock};
 use crate::block::repeat::RepeatBlock;
+use crate::block::sized::SizedBlock;
+use crate::block::source::SourceBlock;
+use crate::block::structure::StructBlock;
+use crate::block::text::TextBlock;
 use crate::block::{Block, MacroBlock, RawMacroBlock};
 use crate::error::EvaluationError;
 use std::rc::Rc;
 
-pub struct Parser {
+pub struct Parser<'a> {
     cursor: ParserCursor,
     indentation: ParserIndentation,
+    file: Rc<String>,
+    path: PathBuf,
+    search_paths: &'a [PathBuf],
+    includes: Vec<(PathBuf, String)>,
+    lexical: bool,
 }
 
 fn tokenize_macro(mut line: String) -> Result<(String, Vec<String>), AnonymousEvaluationError> {
@@ -36,55 +53,412 @@ fn tokenize_macro(mut line: String) -> Result<(String, Vec<String>), AnonymousEv
     }
 }
 
-impl Parser {
-    pub fn new(reader: BufReader<File>) -> Result<Self, EvaluationError> {
+fn tokenize_path(line: &str) -> Result<(String, Vec<String>), AnonymousEvaluationError> {
+    let line: &str = line.trim_start();
+    let end: Option<usize> = line.strip_prefix('"').and_then(|rest| rest.find('"'));
+    match end {
+        Some(end) => Ok((
+            String::from(&line[1..end + 1]),
+            line[end + 2..]
+                .split('#')
+                .next()
+                .unwrap()
+                .split_ascii_whitespace()
+                .map(String::from)
+                .collect(),
+        )),
+        None => Err(AnonymousEvaluationError::new(
+            "expected a quoted file path".to_string(),
+        )),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut characters = name.chars();
+    match characters.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            characters.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn continues_blob(line: &str) -> bool {
+    let mut quote: Option<char> = None;
+    let mut blob: bool = false;
+    let mut previous: char = ' ';
+    let mut characters = line.chars();
+    while let Some(character) = characters.next() {
+        match quote {
+            Some(_) if character == '\\' => {
+                characters.next();
+            }
+            Some(open) if character == open => quote = None,
+            Some(_) => {}
+            None if character == '#' => break,
+            None if character == '"' || character == '\'' => {
+                quote = Some(character);
+                blob = character == '"' && previous == 'x';
+            }
+            None => {}
+        }
+        previous = character;
+    }
+    quote.is_some() && blob
+}
+
+// The first = outside of quotes and comments, which separates @let names from their value
+fn find_assignment(line: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let mut characters = line.char_indices();
+    while let Some((index, character)) = characters.next() {
+        match quote {
+            Some(_) if character == '\\' => {
+                characters.next();
+            }
+            Some(open) if character == open => quote = None,
+            Some(_) => {}
+            None if character == '#' => break,
+            None if character == '"' || character == '\'' => quote = Some(character),
+            None if character == '=' => return Some(index),
+            None => {}
+        }
+    }
+    None
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(
+        path: &Path,
+        search_paths: &'a [PathBuf],
+        mut includes: Vec<(PathBuf, String)>,
+    ) -> Result<Self, AnonymousEvaluationError> {
+        let file: String = path.display().to_string();
+        let reader: BufReader<File> = match File::open(path) {
+            Ok(file) => BufReader::new(file),
+            Err(_) => {
+                return Err(AnonymousEvaluationError::new(format!(
+                    "error reading file {}",
+                    file
+                )))
+            }
+        };
+        if let Ok(canonical) = path.canonicalize() {
+            includes.push((canonical, file.clone()));
+        }
         Ok(Self {
             cursor: ParserCursor::new(reader.lines()),
             indentation: ParserIndentation::new(),
+            file: Rc::new(file),
+            path: path.to_path_buf(),
+            search_paths,
+            includes,
+            lexical: false,
         })
     }
 
+    fn resolve(&self, name: &str) -> Result<PathBuf, AnonymousEvaluationError> {
+        let relative: PathBuf = match self.path.parent() {
+            Some(directory) => directory.join(name),
+            None => PathBuf::from(name),
+        };
+        if relative.is_file() {
+            return Ok(relative);
+        }
+        for directory in self.search_paths.iter() {
+            let candidate: PathBuf = directory.join(name);
+            if candidate.is_file() {
+                return Ok(candidate);
+            }
+        }
+        Err(AnonymousEvaluationError::new(format!(
+            "could not find file {}",
+            name
+        )))
+    }
+
+    fn parse_file(&self, name: &str) -> Result<Vec<Rc<dyn Block>>, EvaluationError> {
+        let line_number: usize = self.cursor.get_line_number();
+        let path: PathBuf = self.resolve(name).map_err_at(line_number)?;
+        if let Ok(canonical) = path.canonicalize() {
+            if let Some(index) = self.includes.iter().position(|(p, _)| *p == canonical) {
+                let mut cycle: Vec<&str> = self.includes[index..]
+                    .iter()
+                    .map(|(_, file)| file.as_str())
+                    .collect();
+                cycle.push(&self.includes[index].1);
+                return Err(EvaluationError::new(
+                    line_number,
+                    format!("include cycle {}", cycle.join(" -> ")),
+                ));
+            }
+        }
+        let mut parser: Parser =
+            Parser::new(&path, self.search_paths, self.includes.clone()).map_err_at(line_number)?;
+        parser.parse_all()
+    }
+
+    fn parse_all(&mut self) -> Result<Vec<Rc<dyn Block>>, EvaluationError> {
+        self.parse(0).map_err(|e| e.in_file(&self.file))
+    }
+
     fn parse_raw(&mut self, level: usize) -> Result<Vec<String>, EvaluationError> {
         let mut result: Vec<String> = Vec::new();
-        while self.cursor.advance()?
-            && self
+        let mut blank: usize = 0;
+        while self.cursor.advance()? {
+            let line: &String = self.cursor.get_line();
+            // Blank lines indented to the block belong to it, even at its end
+            if line.trim().is_empty() && !line.is_empty() && self.indentation.reaches(line, level) {
+                result.resize(result.len() + blank, String::new());
+                blank = 0;
+                result.push(self.indentation.trim(line, level));
+            } else if line.trim().is_empty() {
+                blank += 1;
+            } else if self
                 .indentation
                 .ge(&self.cursor.get_line(), level)
                 .map_err_at(self.cursor.get_line_number())?
-        {
-            result.push(self.indentation.trim(&self.cursor.get_line(), level));
+            {
+                result.resize(result.len() + blank, String::new());
+                blank = 0;
+                result.push(self.indentation.trim(&self.cursor.get_line(), level));
+            } else {
+                self.cursor.hold();
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    // Whether an indented block follows, even if it holds nothing but comments
+    fn has_block(&mut self, level: usize) -> Result<bool, EvaluationError> {
+        while self.cursor.advance()? {
+            if !self.cursor.get_line().trim().is_empty() {
+                self.cursor.hold();
+                return Ok(self.indentation.exceeds(self.cursor.get_line(), level));
+            }
+        }
+        Ok(false)
+    }
+
+    fn parse_conditional(
+        &mut self,
+        level: usize,
+        condition: &str,
+    ) -> Result<IfBlock, EvaluationError> {
+        let mut result: IfBlock = IfBlock::new(
+            self.cursor.get_line_number(),
+            condition,
+            self.parse(level + 1)?,
+        )?;
+        while self.cursor.advance()? {
+            let line: String = String::from(self.cursor.get_line().trim());
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            } else if !self
+                .indentation
+                .eq(&self.cursor.get_line(), level)
+                .map_err_at(self.cursor.get_line_number())?
+            {
+                self.cursor.hold();
+                break;
+            }
+
+            let line_number: usize = self.cursor.get_line_number();
+            let (macro_name, args): (String, Vec<String>) =
+                tokenize_macro(line.clone()).map_err_at(line_number)?;
+            match macro_name.as_str() {
+                "@elif" => {
+                    let blocks: Vec<Rc<dyn Block>> = self.parse(level + 1)?;
+                    result.add_elif(line_number, &line[macro_name.len()..], blocks)?;
+                }
+                "@else" if args.is_empty() => {
+                    let blocks: Vec<Rc<dyn Block>> = self.parse(level + 1)?;
+                    result.add_else(line_number, blocks)?;
+                }
+                "@else" => {
+                    return Err(EvaluationError::new(
+                        line_number,
+                        "expected no arguments after @else".to_string(),
+                    ))
+                }
+                _ => {
+                    self.cursor.hold();
+                    break;
+                }
+            }
         }
         Ok(result)
     }
 
     fn parse(&mut self, level: usize) -> Result<Vec<Rc<dyn Block>>, EvaluationError> {
         let mut result: Vec<Rc<dyn Block>> = Vec::new();
-        while self.cursor.advance()?
-            && self
+        while self.cursor.advance()? {
+            let line: String = String::from(self.cursor.get_line().trim());
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            } else if !self
                 .indentation
                 .eq(&self.cursor.get_line(), level)
                 .map_err_at(self.cursor.get_line_number())?
-        {
-            let line: String = String::from(self.cursor.get_line().trim());
+            {
+                self.cursor.hold();
+                break;
+            }
+
             if line.starts_with("@") {
                 let (macro_name, args): (String, Vec<String>) =
-                    tokenize_macro(line).map_err_at(self.cursor.get_line_number())?;
+                    tokenize_macro(line.clone()).map_err_at(self.cursor.get_line_number())?;
                 match macro_name.as_str() {
                     "@repeat" => result.push(RepeatBlock::allocate(
                         self.cursor.get_line_number(),
                         args,
                         self.parse(level + 1)?,
                     )?),
-                    "@define" => result.push(DefineBlock::allocate(
+                    "@define" => result.push(DefineBlock::with_scoping(
+                        self.cursor.get_line_number(),
+                        &line[macro_name.len()..],
+                        self.parse(level + 1)?,
+                        self.lexical,
+                    )?),
+                    "@scope" => {
+                        let lexical: bool = match args.as_slice() {
+                            [mode] if mode == "lexical" => true,
+                            [mode] if mode == "dynamic" => false,
+                            _ => {
+                                return Err(EvaluationError::new(
+                                    self.cursor.get_line_number(),
+                                    "expected either lexical or dynamic scoping".to_string(),
+                                ))
+                            }
+                        };
+                        let previous: bool = std::mem::replace(&mut self.lexical, lexical);
+                        if self.has_block(level)? {
+                            result.extend(self.parse(level + 1)?);
+                            self.lexical = previous;
+                        }
+                    }
+                    "@let" => match find_assignment(&line) {
+                        Some(index) => result.push(LetBlock::allocate(
+                            self.cursor.get_line_number(),
+                            tokenize_macro(String::from(&line[..index]))
+                                .map_err_at(self.cursor.get_line_number())?
+                                .1,
+                            vec![Rc::new(BytesBlock::new(
+                                self.cursor.get_line_number(),
+                                String::from(&line[index + 1..]),
+                            )?)],
+                        )?),
+                        None => result.push(LetBlock::allocate(
+                            self.cursor.get_line_number(),
+                            args,
+                            self.parse(level + 1)?,
+                        )?),
+                    },
+                    "@if" => result.push(Rc::new(
+                        self.parse_conditional(level, &line[macro_name.len()..])?,
+                    )),
+                    "@for" => result.push(Rc::new(ForBlock::new(
+                        self.cursor.get_line_number(),
+                        &line[macro_name.len()..],
+                        self.parse(level + 1)?,
+                    )?)),
+                    "@namespace" => result.push(ModuleBlock::allocate(
+                        self.cursor.get_line_number(),
+                        args,
+                        self.parse(level + 1)?,
+                    )?),
+                    "@export" => result.push(ExportBlock::allocate(
+                        self.cursor.get_line_number(),
+                        args,
+                        self.parse(level + 1)?,
+                    )?),
+                    "@sized" => result.push(SizedBlock::allocate(
+                        self.cursor.get_line_number(),
+                        args,
+                        self.parse(level + 1)?,
+                    )?),
+                    "@label" => result.push(LabelBlock::allocate(
+                        self.cursor.get_line_number(),
+                        args,
+                        self.parse(level + 1)?,
+                    )?),
+                    "@base" => result.push(BaseBlock::allocate(
                         self.cursor.get_line_number(),
                         args,
                         self.parse(level + 1)?,
                     )?),
+                    "@include" => {
+                        let line_number: usize = self.cursor.get_line_number();
+                        let (name, args): (String, Vec<String>) =
+                            tokenize_path(&line[macro_name.len()..]).map_err_at(line_number)?;
+                        if !args.is_empty() {
+                            return Err(EvaluationError::new(
+                                line_number,
+                                "expected only a file path after @include".to_string(),
+                            ));
+                        }
+                        result.extend(self.parse_file(&name)?);
+                    }
+                    "@import" => {
+                        let line_number: usize = self.cursor.get_line_number();
+                        let (name, args): (String, Vec<String>) =
+                            tokenize_path(&line[macro_name.len()..]).map_err_at(line_number)?;
+                        match args.as_slice() {
+                            [keyword, namespace] if keyword == "as" && is_identifier(namespace) => {
+                                result.push(Rc::new(ModuleBlock::import(
+                                    line_number,
+                                    namespace.clone(),
+                                    self.parse_file(&name)?,
+                                )))
+                            }
+                            _ => {
+                                return Err(EvaluationError::new(
+                                    line_number,
+                                    "expected an import of the form @import \"path\" as name"
+                                        .to_string(),
+                                ))
+                            }
+                        }
+                    }
+                    "@align" | "@pad_to" | "@org" => result.push(Rc::new(PaddingBlock::new(
+                        self.cursor.get_line_number(),
+                        &macro_name,
+                        &line[macro_name.len()..],
+                    )?)),
+                    "@assert" | "@error" | "@warn" | "@print" => {
+                        result.push(Rc::new(DiagnosticBlock::new(
+                            self.cursor.get_line_number(),
+                            &macro_name,
+                            &line[macro_name.len()..],
+                        )?))
+                    }
+                    "@elif" | "@else" => {
+                        return Err(EvaluationError::new(
+                            self.cursor.get_line_number(),
+                            format!("{} without a preceding @if", macro_name),
+                        ))
+                    }
                     "@assembly" => result.push(AssemblyBlock::allocate(
                         self.cursor.get_line_number(),
                         args,
                         self.parse_raw(level + 1)?,
                     )?),
+                    "@struct" => result.push(StructBlock::allocate(
+                        self.cursor.get_line_number(),
+                        args,
+                        self.parse_raw(level + 1)?,
+                    )?),
+                    "@text" => result.push(TextBlock::allocate(
+                        self.cursor.get_line_number(),
+                        args,
+                        self.parse_raw(level + 1)?,
+                    )?),
+                    "@hex" => result.push(HexBlock::allocate(
+                        self.cursor.get_line_number(),
+                        args,
+                        self.parse_raw(level + 1)?,
+                    )?),
                     _ => {
                         return Err(EvaluationError::new(
                             self.cursor.get_line_number(),
@@ -93,17 +467,23 @@ impl Parser {
                     }
                 };
             } else {
-                result.push(Rc::new(BytesBlock::new(
-                    self.cursor.get_line_number(),
-                    line,
-                )?));
+                let line_number: usize = self.cursor.get_line_number();
+                let mut line: String = line;
+                while continues_blob(&line) && self.cursor.advance()? {
+                    line.push('\n');
+                    line.push_str(self.cursor.get_line());
+                }
+                result.push(Rc::new(BytesBlock::new(line_number, line)?));
             }
         }
-        Ok(result)
+        Ok(result
+            .into_iter()
+            .map(|block| SourceBlock::new(self.file.clone(), block) as Rc<dyn Block>)
+            .collect())
     }
 }
 
-pub fn parse(reader: BufReader<File>) -> Result<Vec<Rc<dyn Block>>, EvaluationError> {
-    let mut parser: Parser = Parser::new(reader)?;
-    parser.parse(0)
+pub fn parse(path: &Path, search_paths: &[PathBuf]) -> Result<Vec<Rc<dyn Block>>, EvaluationError> {
+    let mut parser: Parser = Parser::new(path, search_paths, Vec::new()).map_err_at(0)?;
+    parser.parse_all()
 }
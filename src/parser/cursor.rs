@@ -7,6 +7,7 @@ pub struct ParserCursor {
     line: String,
     line_number: usize,
     lines: Lines<BufReader<File>>,
+    held: bool,
 }
 
 impl ParserCursor {
@@ -15,10 +16,15 @@ impl ParserCursor {
             line: String::new(),
             line_number: 0,
             lines,
+            held: false,
         }
     }
 
     pub fn advance(&mut self) -> Result<bool, EvaluationError> {
+        if self.held {
+            self.held = false;
+            return Ok(true);
+        }
         match self.lines.next() {
             None => Ok(false),
             Some(line) => match line {
@@ -35,6 +41,10 @@ impl ParserCursor {
         }
     }
 
+    pub fn hold(&mut self) {
+        self.held = true;
+    }
+
     pub fn get_line(&self) -> &String {
         return &self.line;
     }
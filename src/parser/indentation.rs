@@ -83,6 +83,28 @@ impl ParserIndentation {
         Ok(indentation_level >= level)
     }
 
+    // Unlike ge, this never settles the indentation style, so it is safe to use on comments
+    pub fn exceeds(&self, line: &str, level: usize) -> bool {
+        match self.indentation {
+            Some(Indentation::Spaces(count)) => {
+                line.chars().take_while(|c| *c == ' ').count() > count * level
+            }
+            Some(Indentation::Tabs) => line.chars().take_while(|c| *c == '\t').count() > level,
+            None => line.starts_with([' ', '\t']),
+        }
+    }
+
+    // Like exceeds, but also true for a line indented exactly to the level
+    pub fn reaches(&self, line: &str, level: usize) -> bool {
+        match self.indentation {
+            Some(Indentation::Spaces(count)) => {
+                line.chars().take_while(|c| *c == ' ').count() >= count * level
+            }
+            Some(Indentation::Tabs) => line.chars().take_while(|c| *c == '\t').count() >= level,
+            None => level == 0 || line.starts_with([' ', '\t']),
+        }
+    }
+
     pub fn trim(&self, line: &String, level: usize) -> String {
         line.chars()
             .skip(match self.indentation {
@@ -0,0 +1,847 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::{tempdir, NamedTempFile};
+
+// Runs a script through the binary, yielding its output or its error message
+fn run(script: &str) -> Result<Vec<u8>, String> {
+    run_with(script, &[])
+}
+
+fn run_with(script: &str, options: &[&str]) -> Result<Vec<u8>, String> {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(script.as_bytes()).unwrap();
+    run_file(file.path(), options)
+}
+
+fn run_file(path: &Path, options: &[&str]) -> Result<Vec<u8>, String> {
+    let output = execute(path, options);
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
+
+fn execute(path: &Path, options: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_htor"))
+        .args(options)
+        .arg(path)
+        .output()
+        .unwrap()
+}
+
+// Runs a script that succeeds, checking both its output and what it printed to stderr
+fn expect_stderr(script: &str, expected: &[u8], messages: &[&str]) {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(script.as_bytes()).unwrap();
+    let output: Output = execute(file.path(), &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "unexpected error: {}", stderr);
+    assert_eq!(output.stdout, expected);
+    for message in messages {
+        assert!(
+            stderr.contains(message),
+            "missing {:?} in {}",
+            message,
+            stderr
+        );
+    }
+}
+
+fn expect(script: &str, expected: &[u8]) {
+    assert_eq!(run(script), Ok(expected.to_vec()));
+}
+
+fn expect_error(script: &str, message: &str) {
+    match run(script) {
+        Ok(output) => panic!("expected an error, got {:?}", output),
+        Err(error) => assert!(error.contains(message), "unexpected error: {}", error),
+    }
+}
+
+#[test]
+fn if_compares_length_with_bare_integer() {
+    let script = "\
+@define x
+    00 01 02 03 04 05 06 07 08
+@if len($x) > 8
+    aa
+@else
+    bb
+";
+    expect(script, &[0xaa]);
+}
+
+#[test]
+fn if_compares_bytes_with_bare_integer() {
+    let script = "\
+@define x
+    05
+@if $x < 8
+    aa
+@if $x > 0
+    bb
+@if $x == 05
+    cc
+";
+    expect(script, &[0xaa, 0xbb, 0xcc]);
+}
+
+#[test]
+fn if_selects_branch_by_canary() {
+    let script = "\
+@define canary
+    00 11 22 33 44 55 66 77
+@if defined($canary) && len($canary) == 0d8
+    $canary
+@elif defined($canary)
+    @error \"canary must be 8 bytes\"
+@else
+    ff
+";
+    expect(script, &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]);
+    expect("@if defined($canary)\n    aa\n@else\n    ff\n", &[0xff]);
+}
+
+#[test]
+fn if_rejects_dangling_else() {
+    expect_error("aa\n@else\n    bb\n", "@else");
+}
+
+const SOCKADDR: &str = "\
+@struct sockaddr
+    u16le family
+    u8[8] pad
+    ptr64 vtable
+";
+
+#[test]
+fn struct_lays_out_fields_with_c_alignment() {
+    let script = format!(
+        "{}$sizeof(sockaddr) $offsetof(sockaddr, vtable)\n",
+        SOCKADDR
+    );
+    expect(&script, &[0x18, 0x10]);
+}
+
+#[test]
+fn struct_instantiates_with_named_fields() {
+    let script = format!("{}aa $sockaddr(family=2, vtable=$here)\n", SOCKADDR);
+    let mut expected: Vec<u8> = vec![0xaa, 0x02, 0x00];
+    expected.extend(vec![0; 14]);
+    expected.extend(vec![0x11, 0, 0, 0, 0, 0, 0, 0]);
+    expect(&script, &expected);
+}
+
+#[test]
+fn struct_packs_fields() {
+    let script = "\
+@struct header packed
+    u8 kind
+    u32be length
+$sizeof(header) $header(1, 0d258)
+";
+    expect(script, &[0x05, 0x01, 0x00, 0x00, 0x01, 0x02]);
+}
+
+#[test]
+fn struct_array_counts_are_hexadecimal() {
+    let script = "\
+@struct buffers
+    u8[10] hex
+    u8[0d10] decimal
+$offsetof(buffers, decimal) $sizeof(buffers)
+";
+    expect(script, &[0x10, 0x1a]);
+}
+
+#[test]
+fn struct_reports_field_line() {
+    let script = "\
+@struct broken
+    u16le family
+    u17 port
+";
+    expect_error(script, "line 3");
+    expect_error("@struct twice\n    u8 a\n\n    u8 a\n", "line 4");
+}
+
+#[test]
+fn label_resolves_forward_reference() {
+    let script = "\
+@base 0x7fffffffe000
+$pack(u64le, $addr(shell))
+$pack(u64le, $here)
+@label shell
+\"/bin/sh\" 00
+";
+    let mut expected: Vec<u8> = vec![0x10, 0xe0, 0xff, 0xff, 0xff, 0x7f, 0, 0];
+    expected.extend(&[0x08, 0xe0, 0xff, 0xff, 0xff, 0x7f, 0, 0]);
+    expected.extend(b"/bin/sh\0");
+    expect(script, &expected);
+}
+
+#[test]
+fn label_is_local_to_each_expansion() {
+    let script = "\
+@define stub
+    @label entry
+    90 u8:$offset(entry)
+$stub $stub
+";
+    expect(script, &[0x90, 0x00, 0x90, 0x02]);
+}
+
+#[test]
+fn label_is_local_to_each_iteration() {
+    let script = "\
+@for i in 0..2
+    @label top
+    u8:$offset(top) u8:$offset(end)
+    @label end
+";
+    expect(script, &[0x00, 0x02, 0x02, 0x04]);
+}
+
+#[test]
+fn label_rejects_duplicate() {
+    expect_error(
+        "@label a\naa\n@label a\n",
+        "label a is already defined on line 1",
+    );
+}
+
+#[test]
+fn label_reports_line_that_did_not_converge() {
+    let script = "\
+@if $offset(b) == 0
+    aa
+    @label b
+";
+    expect_error(script, "line 3: label b did not converge");
+}
+
+#[test]
+fn repeat_accepts_literals_and_expressions() {
+    expect("@repeat 0x4\n    41\n", b"AAAA");
+    expect("@repeat 0d5\n    41\n", b"AAAAA");
+    let script = "\
+@define offset
+    (0d72)
+@repeat $offset - 0d8
+    41
+";
+    expect(script, &[0x41; 64]);
+}
+
+#[test]
+fn repeat_reads_bare_numbers_as_hexadecimal() {
+    expect("@repeat 10\n    41\n", &[0x41; 16]);
+    expect("@repeat (10)\n    41\n", &[0x41; 16]);
+    expect("@repeat 10 + 0\n    41\n", &[0x41; 16]);
+    expect("@repeat 0d10\n    41\n", &[0x41; 10]);
+    expect("@repeat 0\n    41\n", b"");
+}
+
+#[test]
+fn scope_lexical_ignores_caller_definitions() {
+    let script = "\
+@define fill
+    41
+@scope lexical
+    @define pad n
+        @repeat $n
+            $fill
+@define user
+    @define fill
+        42
+    $pad(3)
+$user
+";
+    expect(script, b"AAA");
+}
+
+#[test]
+fn scope_lexical_nests_definitions() {
+    let script = "\
+@scope lexical
+@define outer x
+    @define inner
+        $x $x
+    $inner
+@define x
+    ff
+$outer(41)
+";
+    expect(script, b"AA");
+}
+
+#[test]
+fn scope_block_of_comments_does_not_leak() {
+    let script = "\
+@define fill
+    41
+@scope lexical
+    # nothing here yet
+@define pad
+    $fill
+@define user
+    @define fill
+        42
+    $pad
+$user
+";
+    expect(script, b"B");
+}
+
+#[test]
+fn numeral_reads_back_as_integer() {
+    let script = "\
+@define offset
+    48
+@repeat $offset - 0d70
+    41
+[4]($offset + 1)
+";
+    expect(script, &[0x41, 0x41, 0x00, 0x00, 0x00, 0x49]);
+}
+
+#[test]
+fn typed_bytes_require_int() {
+    let script = "\
+@define size
+    u32le:0x10
+($size + 1)
+";
+    expect_error(script, "without $int");
+    expect(
+        "@define size\n    u32le:0x10\n($int($size, le) + 1)\n",
+        &[0x11],
+    );
+}
+
+#[test]
+fn hex_reads_free_form_words() {
+    let script = "\
+@hex
+  de ad be ef  # comment
+  0x90, 0x90, \\x90
+";
+    expect(script, &[0xde, 0xad, 0xbe, 0xef, 0x90, 0x90, 0x90]);
+}
+
+#[test]
+fn hex_reads_xxd_output() {
+    let script = "\
+@hex
+  00000000: 4865 6c6c 6f2c 2064 756d 7023 3132 3334  Hello, dump#1234
+  00000010: 0a                                       .
+";
+    expect(script, b"Hello, dump#1234\n");
+}
+
+#[test]
+fn hex_reads_hexdump_output() {
+    let script = "\
+@hex
+  00000000  41 41 41 41 41 41 41 41  41 41 41 41 41 41 41 41  |AAAAAAAAAAAAAAAA|
+  *
+  00000030  62 63 7c 0a                                       |bc|.|
+  00000034
+";
+    let mut expected: Vec<u8> = vec![0x41; 0x30];
+    expected.extend(b"bc|\n");
+    expect(script, &expected);
+}
+
+#[test]
+fn define_uses_defaults_and_named_arguments() {
+    let script = "\
+@define pad n=0d8 fill=41
+  @repeat $n
+    $fill
+$pad(fill=90)
+$pad(0d4, fill=00)
+";
+    let mut expected: Vec<u8> = vec![0x90; 8];
+    expected.extend(&[0x00; 4]);
+    expect(script, &expected);
+}
+
+#[test]
+fn define_accepts_defaults_with_spaces() {
+    let script = "\
+@define pad n=(0d1 + 1) fill=41 42 tail=\"a  b\"
+  @repeat $n
+    $fill
+  $tail
+$pad
+";
+    expect(script, b"ABABa  b");
+}
+
+#[test]
+fn define_rejects_malformed_default() {
+    expect_error("@define pad n=(0d8 +\n  00\n", "line 1");
+    expect_error("@define pad n=\n  00\n", "line 1");
+}
+
+#[test]
+fn let_splits_on_first_bare_equals() {
+    expect("@let s = \"a=b\" 3d\n$s\n", b"a=b=");
+    expect("@let s # x=y\n  41\n$s\n", b"A");
+}
+
+#[test]
+fn shift_rejects_huge_amounts() {
+    expect("[2](1 << 0d8)\n", &[0x01, 0x00]);
+    expect_error("(1 << 0d100000000000)\n", "exceeds the limit");
+    expect("[1](0d1000 >> 0d100000000000)\n", &[0x00]);
+}
+
+#[test]
+fn negative_literal_rejects_overflow() {
+    expect("[1]-0d128 [2]-01 [1](-0d128)\n", &[0x80, 0xff, 0xff, 0x80]);
+    expect_error("[1]-0d200\n", "does not fit in 1 bytes");
+    expect_error("[1]-0d129\n", "does not fit in 1 bytes");
+    expect_error("[1](-0d200)\n", "does not fit in 1 bytes");
+}
+
+#[test]
+fn sized_patches_length_before_body() {
+    let script = "\
+@sized u16be
+  01 u8:$here
+  @sized u8
+    @label inner
+    u8:$offset(inner) \"ab\"
+";
+    expect(script, &[0x00, 0x06, 0x01, 0x03, 0x03, 0x05, 0x61, 0x62]);
+}
+
+#[test]
+fn blob_reports_continuation_line() {
+    expect(
+        "aa x\"0011\n  22 33\" bb\n",
+        &[0xaa, 0x00, 0x11, 0x22, 0x33, 0xbb],
+    );
+    expect_error(
+        "aa x\"0011\n  22 3g\"\n",
+        "line 2: invalid hexadecimal digit 'g'",
+    );
+    expect_error(
+        "aa x\"0011\n  22 3\n\"\n",
+        "line 2: length of hexadecimal word",
+    );
+    expect_error("aa\nx\"00\n  11\" zz\n", "line 3");
+}
+
+#[test]
+fn string_reports_column_in_characters() {
+    expect_error("\"é\\q\"\n", "invalid escape sequence \\q in column 3");
+    expect_error(
+        "\"é\" u8\"éé\\q\"\n",
+        "invalid escape sequence \\q in column 10",
+    );
+}
+
+#[test]
+fn align_pads_to_multiple() {
+    expect("aa\n@align 04\nbb\n", &[0xaa, 0x00, 0x00, 0x00, 0xbb]);
+    expect("aa\n@align (0d2 + 2) 90\n", &[0xaa, 0x90, 0x90, 0x90]);
+    expect_error("aa\n@align 0\n", "line 2: invalid alignment 0");
+}
+
+#[test]
+fn pad_to_accepts_expression_and_fill() {
+    let script = "\
+@define start
+    (2)
+aa
+@pad_to $start + 0d2 41 42
+bb
+";
+    expect(script, &[0xaa, 0x41, 0x42, 0x41, 0xbb]);
+    expect("@pad_to (1 << 2)\n", &[0x00; 4]);
+    expect_error("aa bb\n@pad_to 1\n", "line 2: cannot pad to offset 1");
+}
+
+#[test]
+fn pad_to_rejects_huge_target() {
+    expect_error("@pad_to 0xffffffffffffff\n", "exceeds the limit");
+}
+
+#[test]
+fn org_pads_relative_to_base() {
+    let script = "\
+@base 0x400000
+aa
+@org 0x400000 + 3 cc
+bb
+";
+    expect(script, &[0xaa, 0xcc, 0xcc, 0xbb]);
+    expect_error(
+        "@base 10\naa\n@org 0f\n",
+        "line 3: address 0xf is below the base address",
+    );
+}
+
+#[test]
+fn hex_keeps_bare_words_after_hexdump() {
+    let script = "\
+@hex
+  00000000  41 42                                             |AB|
+  0a0b
+  00000004
+";
+    expect(script, &[0x41, 0x42, 0x0a, 0x0b]);
+    let script = "\
+@hex
+  00000000  41 42                                             |AB|
+  0a0b
+";
+    expect(script, &[0x41, 0x42, 0x0a, 0x0b]);
+}
+
+#[test]
+fn text_joins_lines_verbatim() {
+    expect("@text\n  a # b\n\n  c\n", b"a # b\n\nc\n");
+    expect("@text nonewline\n  a\n  b\n", b"a\nb");
+}
+
+#[test]
+fn text_keeps_indented_trailing_blank_lines() {
+    let script = "@text crlf\n  GET / HTTP/1.1\n  Host: localhost\n  \n\naa\n";
+    expect(script, b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n\xaa");
+    expect("@text\n  a\n\n\naa\n", b"a\n\xaa");
+}
+
+#[test]
+fn character_reports_line_and_column() {
+    expect("'A' '\\n' '\\x90'\n", &[0x41, 0x0a, 0x90]);
+    expect_error("\"é\" 'ab'\n", "line 1: character literal in column 5");
+    expect_error(
+        "aa x\"00\n11\" 'ab'\n",
+        "line 2: character literal in column 5",
+    );
+    expect_error(
+        "aa x\"00\n11\" \"\\q\"\n",
+        "line 2: invalid escape sequence \\q in column 6",
+    );
+}
+
+#[test]
+fn lazy_parameter_expands_at_each_use() {
+    let script = "\
+@define twice &x
+    $x $x
+@define eager x
+    $x $x
+$twice(u8:$here) $eager(u8:$here)
+";
+    expect(script, &[0x00, 0x01, 0x02, 0x02]);
+}
+
+#[test]
+fn lazy_parameter_unused_is_never_expanded() {
+    expect("@define ignore &x\n    aa\n$ignore($missing)\n", &[0xaa]);
+    expect_error("@define ignore x\n    aa\n$ignore($missing)\n", "missing");
+}
+
+#[test]
+fn recursion_reports_expansion_and_limit() {
+    let script = "@define f\n    $f\n$f\n";
+    expect_error(script, "recursion depth exceeded at $f (limit 256)");
+    match run_with(script, &["--recursion-limit", "0d10"]) {
+        Ok(output) => panic!("expected an error, got {:?}", output),
+        Err(error) => assert!(error.contains("invalid recursion limit"), "{}", error),
+    }
+    match run_with(script, &["--recursion-limit", "10"]) {
+        Ok(output) => panic!("expected an error, got {:?}", output),
+        Err(error) => assert!(error.contains("(limit 10)"), "{}", error),
+    }
+}
+
+#[test]
+fn recursion_limit_has_maximum() {
+    match run_with("aa\n", &["--recursion-limit", "100000"]) {
+        Ok(output) => panic!("expected an error, got {:?}", output),
+        Err(error) => assert!(error.contains("exceeds the maximum of 8192"), "{}", error),
+    }
+    assert_eq!(
+        run_with("aa\n", &["--recursion-limit", "8192"]),
+        Ok(vec![0xaa])
+    );
+}
+
+// Writes each file into a fresh directory, then runs the first of them
+fn run_files(files: &[(&str, &str)], options: &[&str]) -> Result<Vec<u8>, String> {
+    let directory = tempdir().unwrap();
+    for (name, contents) in files {
+        let path = directory.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    let options: Vec<String> = options
+        .iter()
+        .map(|option| option.replace("{}", directory.path().to_str().unwrap()))
+        .collect();
+    let options: Vec<&str> = options.iter().map(String::as_str).collect();
+    run_file(&directory.path().join(files[0].0), &options)
+}
+
+#[test]
+fn include_parses_file_in_place() {
+    let files = [
+        (
+            "main.txt",
+            "@include \"lib.txt\"\n$x 42\n@repeat 2\n    @include \"lib.txt\"\n",
+        ),
+        ("lib.txt", "@define x\n    41\n43\n"),
+    ];
+    assert_eq!(run_files(&files, &[]), Ok(b"CABCC".to_vec()));
+}
+
+#[test]
+fn import_qualifies_definitions() {
+    let files = [
+        (
+            "main.txt",
+            "@import \"gadgets.txt\" as g\n$g.pair $g::pop\n",
+        ),
+        (
+            "gadgets.txt",
+            "@define pop\n    5f\n@define pair\n    $pop $pop\n",
+        ),
+    ];
+    assert_eq!(run_files(&files, &[]), Ok(vec![0x5f; 3]));
+    let files = [
+        ("main.txt", "@import \"gadgets.txt\" as g\n$pop\n"),
+        ("gadgets.txt", "@define pop\n    5f\n"),
+    ];
+    assert!(run_files(&files, &[]).unwrap_err().contains("line 2"));
+}
+
+#[test]
+fn include_searches_directories() {
+    let files = [
+        ("main.txt", "@include \"lib.txt\"\n"),
+        ("extra/lib.txt", "41\n"),
+    ];
+    assert!(run_files(&files, &[]).is_err());
+    assert_eq!(run_files(&files, &["-I", "{}/extra"]), Ok(b"A".to_vec()));
+}
+
+#[test]
+fn include_reports_cycle() {
+    let files = [
+        ("a.txt", "@include \"b.txt\"\n"),
+        ("b.txt", "@import \"a.txt\" as a\n"),
+    ];
+    let error: String = run_files(&files, &[]).unwrap_err();
+    assert!(error.contains("include cycle"), "{}", error);
+}
+
+#[test]
+fn include_reports_error_in_included_file() {
+    let files = [
+        ("main.txt", "aa\n@include \"lib.txt\"\n"),
+        ("lib.txt", "41\n$missing\n"),
+    ];
+    let error: String = run_files(&files, &[]).unwrap_err();
+    assert!(error.contains("lib.txt on line 2"), "{}", error);
+}
+
+#[test]
+fn namespace_qualifies_definitions() {
+    let script = "\
+@define fill
+    41
+@namespace x86
+    @define nop
+        90
+    @define pad
+        $fill $nop
+$x86.nop $x86::pad
+";
+    expect(script, &[0x90, 0x41, 0x90]);
+    expect_error("@namespace a\n    @define b\n        00\n$b\n", "line 4");
+}
+
+#[test]
+fn namespace_nests() {
+    let script = "\
+@namespace a
+    @namespace b
+        @namespace c
+            @define d
+                dd
+$a.b.c.d $a::b::c::d $a.b::c.d
+";
+    expect(script, &[0xdd; 3]);
+}
+
+#[test]
+fn export_limits_visible_names() {
+    let script = "\
+@namespace x86
+    @export sled
+    @define nop
+        90
+    @define sled
+        $nop $nop
+$x86.sled
+";
+    expect(script, &[0x90, 0x90]);
+    let hidden = format!("{}$x86.nop\n", script);
+    expect_error(&hidden, "line 8");
+}
+
+#[test]
+fn export_rejects_undefined_name() {
+    let script = "\
+@namespace x86
+    @export sled
+    @define nop
+        90
+";
+    expect_error(script, "namespace x86 exports undefined name sled");
+}
+
+#[test]
+fn assert_passes_without_output() {
+    expect("aa\n@assert len(aa bb) == 2\nbb\n", &[0xaa, 0xbb]);
+    expect(
+        "aa\n@assert len(aa) == 1 \"never shown\"\nbb\n",
+        &[0xaa, 0xbb],
+    );
+}
+
+#[test]
+fn assert_reports_line_and_message() {
+    expect_error(
+        "aa\n@assert len(aa) == 2 \"too short\"\n",
+        "line 2: assertion failed: too short",
+    );
+    expect_error("aa\nbb\n@assert len(aa) == 2\n", "line 3: assertion failed");
+}
+
+#[test]
+fn error_fails_with_message() {
+    expect_error(
+        "aa\n@if 1\n    @error \"unsupported\"\n",
+        "line 3: unsupported",
+    );
+}
+
+#[test]
+fn warn_and_print_leave_payload_unchanged() {
+    let script = "\
+aa
+@warn \"careful\"
+@print 41 (0d66)
+bb
+";
+    expect_stderr(
+        script,
+        &[0xaa, 0xbb],
+        &["Warning on line 2: careful", "Line 3: 41 42"],
+    );
+}
+
+#[test]
+fn decimal_exceeds_sixty_four_bits() {
+    let mut expected: Vec<u8> = vec![0x01];
+    expected.extend(&[0x00; 8]);
+    expect("0d18446744073709551616\n", &expected);
+    expect(
+        "[4]0d18446744073709551617 0d18446744073709551617[2] 0d1_000\n",
+        &[0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x03, 0xe8],
+    );
+}
+
+#[test]
+fn float_selects_precision_by_size() {
+    let mut expected: Vec<u8> = vec![0x3f, 0xc0, 0x00, 0x00, 0x3f, 0xf8];
+    expected.extend(&[0x00; 6]);
+    expected.extend(&[0x3f, 0xf8, 0, 0, 0, 0, 0, 0]);
+    expect("[4]0f1.5 [8]0f1.5 0f1.5\n", &expected);
+    expect("[4]-0f2.0\n", &[0xc0, 0x00, 0x00, 0x00]);
+    expect_error("[2]0f1.5\n", "line 1: float");
+}
+
+#[test]
+fn float_flips_to_little_endian() {
+    expect(
+        "< [4]0f1.5 > [4]0f1.5\n",
+        &[0x00, 0x00, 0xc0, 0x3f, 0x3f, 0xc0, 0x00, 0x00],
+    );
+}
+
+#[test]
+fn typed_literal_encodes_in_range() {
+    expect(
+        "u16le:0x1234 i16be:-01 i8:-0d128 u32be:(0d1 + 1)\n",
+        &[0x34, 0x12, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0x02],
+    );
+}
+
+#[test]
+fn typed_literal_rejects_overflow() {
+    expect_error("aa\nu8:0d256\n", "line 2: value 256 does not fit in u8");
+    expect_error("i8:0d128\n", "value 128 does not fit in i8");
+    expect_error("i8:-0d129\n", "value -129 does not fit in i8");
+    expect_error("u16le:-01\n", "value -1 does not fit in u16le");
+}
+
+#[test]
+fn string_encodes_charsets() {
+    expect(
+        "u16le\"hé\" u16be\"A\" u8\"é\" \"é\"\n",
+        &[0x68, 0x00, 0xe9, 0x00, 0x00, 0x41, 0xc3, 0xa9, 0xe9],
+    );
+    expect("u16be\"\\U0001F600\"\n", &[0xd8, 0x3d, 0xde, 0x00]);
+    expect_error("\"€\"\n", "invalid character in column 2");
+}
+
+#[test]
+fn string_appends_terminator_in_charset() {
+    expect(
+        "z\"ab\" zu16le\"A\" zu8\"é\"\n",
+        &[0x61, 0x62, 0x00, 0x41, 0x00, 0x00, 0x00, 0xc3, 0xa9, 0x00],
+    );
+}
+
+#[test]
+fn for_iterates_over_range() {
+    expect("@for i in 0..4\n    u8:$i\n", &[0x00, 0x01, 0x02, 0x03]);
+    expect("@for i in 0..10\n    00\n", &[0x00; 16]);
+    expect("@for i in 1 + 1..0d2 * 2\n    u8:($i * 2)\n", &[0x04, 0x06]);
+    expect("@for i in 4..2\n    00\n", b"");
+}
+
+#[test]
+fn for_iterates_over_list() {
+    let script = "\
+@define canary
+    ff
+@for x in (41, \"BB\", $canary)
+    $x 00
+";
+    expect(script, &[0x41, 0x00, 0x42, 0x42, 0x00, 0xff, 0x00]);
+}
+
+#[test]
+fn for_iterates_over_variadic_parameter() {
+    let script = "\
+@define table base rest...
+    @for entry in $rest
+        $base $entry
+    $rest
+$table(aa, 01, 02 03)
+$table(bb)
+";
+    expect(script, &[0xaa, 0x01, 0xaa, 0x02, 0x03, 0x01, 0x02, 0x03]);
+}